@@ -0,0 +1,103 @@
+//! Generates `src/assembler/instruction.rs`'s opcode table from
+//! `instructions.in` so adding an opcode (a new hardfork, a devnet
+//! extension) is a one-line table edit instead of a hand-maintained enum.
+
+use std::{
+    env, fmt::Write as _, fs, path::Path,
+};
+
+struct Instruction {
+    name: String,
+    byte: u8,
+    operand_size: u8,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=instructions.in");
+
+    let source = fs::read_to_string("instructions.in").expect("could not read instructions.in");
+    let instructions = parse(&source);
+
+    let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR not set");
+    let dest = Path::new(&out_dir).join("opcode_table.rs");
+    fs::write(dest, render(&instructions)).expect("could not write opcode_table.rs");
+}
+
+fn parse(source: &str) -> Vec<Instruction> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields
+                .next()
+                .unwrap_or_else(|| panic!("missing name in instructions.in line: {line}"))
+                .to_ascii_uppercase();
+            let byte = fields
+                .next()
+                .unwrap_or_else(|| panic!("missing hex byte in instructions.in line: {line}"));
+            let byte = u8::from_str_radix(byte, 16)
+                .unwrap_or_else(|e| panic!("invalid hex byte in instructions.in line {line}: {e}"));
+            let operand_size = fields
+                .next()
+                .unwrap_or_else(|| panic!("missing operand size in instructions.in line: {line}"));
+            let operand_size: u8 = operand_size
+                .parse()
+                .unwrap_or_else(|e| panic!("invalid operand size in instructions.in line {line}: {e}"));
+
+            Instruction {
+                name,
+                byte,
+                operand_size,
+            }
+        })
+        .collect()
+}
+
+fn render(instructions: &[Instruction]) -> String {
+    let mut out = String::new();
+
+    out.push_str("/// Opcode byte -> lowercase mnemonic.\n");
+    out.push_str("pub fn instruction(byte: u8) -> Option<String> {\n");
+    out.push_str("    match byte {\n");
+    for instruction in instructions {
+        writeln!(
+            out,
+            "        0x{:02x} => Some(\"{}\".to_owned()),",
+            instruction.byte,
+            instruction.name.to_ascii_lowercase()
+        )
+        .unwrap();
+    }
+    out.push_str("        _ => None,\n    }\n}\n\n");
+
+    out.push_str("/// Mnemonic (case-insensitive) -> opcode byte.\n");
+    out.push_str("pub fn opcode(name: &str) -> Option<u8> {\n");
+    out.push_str("    match name.to_ascii_uppercase().as_str() {\n");
+    for instruction in instructions {
+        writeln!(
+            out,
+            "        \"{}\" => Some(0x{:02x}),",
+            instruction.name, instruction.byte
+        )
+        .unwrap();
+    }
+    out.push_str("        _ => None,\n    }\n}\n\n");
+
+    out.push_str("/// Number of immediate operand bytes `byte` consumes (e.g. 1 for `PUSH1`,\n");
+    out.push_str("/// 0 for everything that isn't a `PUSH`).\n");
+    out.push_str("pub fn operand_size(byte: u8) -> usize {\n");
+    out.push_str("    match byte {\n");
+    for instruction in instructions.iter().filter(|i| i.operand_size > 0) {
+        writeln!(
+            out,
+            "        0x{:02x} => {},",
+            instruction.byte, instruction.operand_size
+        )
+        .unwrap();
+    }
+    out.push_str("        _ => 0,\n    }\n}\n");
+
+    out
+}
@@ -0,0 +1,90 @@
+use super::{
+    diagnostics::{Diagnostic, Severity, Span},
+    error::AssembleError,
+    gas::{estimate_gas, GasEstimate},
+    labeler::labelize,
+    stack::{verify_stack_balance, StackDiagnostic},
+    tokenizer::{amend, clean_asm, tokenize, tokenize_checked, Token},
+};
+
+/// Assembles Solidity-produced EVM assembly and compressed Stylus wasm into
+/// deployable init code, alongside any non-fatal warnings noticed along the
+/// way (e.g. a block that falls through with unconsumed stack items, from
+/// the stack-balance check) for the caller to surface, and a
+/// static [`GasEstimate`] for the assembled stream so callers can show a
+/// cheap deploy-cost ceiling before broadcasting anything.
+pub fn assemble(evmasm: &str, wasm: &[u8]) -> Result<(Vec<u8>, Vec<Diagnostic>, GasEstimate), AssembleError> {
+    let amended = amend(evmasm, wasm);
+
+    let (tokens, warnings) = match tokenize_checked(&amended) {
+        Ok(tokens) => (tokens, vec![]),
+        Err(diagnostics) => {
+            let (errors, warnings): (Vec<_>, Vec<_>) =
+                diagnostics.into_iter().partition(|d| d.severity == Severity::Error);
+            if !errors.is_empty() {
+                return Err(AssembleError::Diagnostics(errors));
+            }
+
+            // Only warnings: the checked tokenizer refuses to hand back its
+            // token stream, so fall back to the lenient path to actually get
+            // one, but don't let the warnings it already found go unreported.
+            let instructions = clean_asm(&amended)?;
+            (tokenize(instructions), warnings)
+        }
+    };
+
+    let (stack_errors, stack_warnings): (Vec<_>, Vec<_>) =
+        verify_stack_balance(&tokens).into_iter().partition(|d| d.severity == Severity::Error);
+    if !stack_errors.is_empty() {
+        return Err(AssembleError::StackImbalance(stack_errors));
+    }
+    let warnings: Vec<Diagnostic> =
+        warnings.into_iter().chain(stack_warnings.iter().map(stack_diagnostic_as_diagnostic)).collect();
+
+    let tokens = labelize(&tokens)?;
+    let gas_estimate = estimate_gas(&tokens);
+
+    tokens
+        .iter()
+        .map(Token::bytecode)
+        .collect::<Result<Vec<_>, AssembleError>>()
+        .map(|bytes| (bytes.concat(), warnings, gas_estimate))
+}
+
+/// Renders a token-indexed [`StackDiagnostic`] as a [`Diagnostic`], so stack-
+/// balance warnings can be merged into the same `warnings` vec the tokenizer
+/// reports into. There's no byte span to point at (stack analysis works on
+/// the token stream, not source text), so the token's index stands in for
+/// both ends of the [`Span`], same as the disassembler reusing `Span` to
+/// mean "byte offset into raw bytecode" instead of "assembly text".
+fn stack_diagnostic_as_diagnostic(diagnostic: &StackDiagnostic) -> Diagnostic {
+    Diagnostic::warning(diagnostic.message.clone(), Span { start: diagnostic.index, end: diagnostic.index })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, AssembleError};
+
+    #[test]
+    fn rejects_assembly_that_underflows_the_stack() {
+        // `add` pops two items, but nothing was ever pushed.
+        let err = assemble("add", &[]).unwrap_err();
+        assert!(matches!(err, AssembleError::StackImbalance(_)));
+    }
+
+    #[test]
+    fn assembles_valid_input_with_no_warnings() {
+        let (bytecode, warnings, estimate) = assemble("push1 0x01\npop", &[]).unwrap();
+        assert!(!bytecode.is_empty());
+        assert!(warnings.is_empty());
+        assert!(estimate.total() > 0);
+    }
+
+    #[test]
+    fn surfaces_a_stack_balance_warning_instead_of_dropping_it() {
+        // the pushed 0x01 is never popped before falling through to `stop`.
+        let (bytecode, warnings, _) = assemble("push1 0x01\nstop", &[]).unwrap();
+        assert!(!bytecode.is_empty());
+        assert_eq!(1, warnings.len());
+    }
+}
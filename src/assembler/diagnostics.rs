@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The input cannot be assembled as-is.
+    Error,
+    /// The input is assemblable, but likely not what the author intended.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A byte range into whatever input a [`Diagnostic`] was raised from -- the
+/// cleaned assembly text when raised by the tokenizer, or raw bytecode when
+/// raised by the disassembler. A stack-balance warning passed on by
+/// `assemble` instead holds the offending token's index in both `start` and
+/// `end`.
+///
+/// When pointing into assembly text, note this is the *cleaned* assembly --
+/// comments stripped, whitespace normalized, macros expanded -- not the raw
+/// source the user typed, since those transformations aren't tracked back to
+/// their origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A single problem found while analyzing some input, with enough context to
+/// point a user at the offending assembly or bytecode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} (at byte {}..{})",
+            self.severity, self.message, self.span.start, self.span.end
+        )
+    }
+}
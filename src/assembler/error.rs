@@ -0,0 +1,42 @@
+use thiserror::Error;
+
+use super::{stack::StackDiagnostic, Diagnostic};
+
+/// Errors that can occur while assembling an instruction stream into EVM
+/// bytecode.
+#[derive(Debug, Error)]
+pub enum AssembleError {
+    #[error("imbalanced labels at index {index}")]
+    ImbalancedLabels { index: usize },
+    #[error("undefined label '{name}'")]
+    UndefinedLabel { name: String },
+    #[error("label width exceeds the maximum operand size of 32 bytes")]
+    LabelTooLarge,
+    #[error("unexpected token found when generating bytecode: {token}")]
+    UnexpectedToken { token: String },
+    #[error(
+        "macro expansion exceeded {max_depth} levels -- check for a self-referential or \
+         mutually recursive macro definition"
+    )]
+    MacroExpansionTooDeep { max_depth: usize },
+    #[error("invalid assembly:\n{}", format_diagnostics(.0))]
+    Diagnostics(Vec<Diagnostic>),
+    #[error("unbalanced stack:\n{}", format_stack_diagnostics(.0))]
+    StackImbalance(Vec<StackDiagnostic>),
+}
+
+fn format_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_stack_diagnostics(diagnostics: &[StackDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,319 @@
+use super::tokenizer::Token;
+
+/// A static, worst-case gas estimate for deploying and running an assembled
+/// token stream once, straight-line (no account for loops or branches
+/// actually being taken more than once).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// `21000` plus the calldata cost of `stream`'s assembled bytes.
+    pub intrinsic_gas: u64,
+    /// Sum of each opcode's static cost, the linear per-word surcharge
+    /// `SHA3`/`CODECOPY`/`CALLDATACOPY`/`RETURNDATACOPY` charge on top of
+    /// their flat cost, and the memory-expansion cost implied by the
+    /// largest memory offset we could resolve from literal operands.
+    pub execution_gas: u64,
+    /// Set when at least one opcode's true cost depends on data we can't
+    /// see statically (e.g. `SSTORE`'s cold/warm/refund rules, `CALL`'s
+    /// value-transfer surcharge, or a memory op whose offset/size wasn't a
+    /// literal we could trace), in which case `execution_gas` is a lower
+    /// bound rather than an exact figure.
+    pub data_dependent: bool,
+}
+
+impl GasEstimate {
+    /// `intrinsic_gas + execution_gas`.
+    pub fn total(&self) -> u64 {
+        self.intrinsic_gas + self.execution_gas
+    }
+}
+
+const TX_BASE_GAS: u64 = 21_000;
+const TX_ZERO_BYTE_GAS: u64 = 4;
+const TX_NONZERO_BYTE_GAS: u64 = 68;
+
+/// Estimates the gas cost of deploying and running `stream` once,
+/// straight-line, using the standard EVM per-opcode cost table. This is a
+/// cheap ceiling to sanity-check a deploy against before broadcasting it --
+/// not a substitute for actually running the code in an EVM.
+pub fn estimate_gas(stream: &[Token]) -> GasEstimate {
+    let (zero_bytes, nonzero_bytes) = count_calldata_bytes(stream);
+    let intrinsic_gas =
+        TX_BASE_GAS + TX_ZERO_BYTE_GAS * zero_bytes + TX_NONZERO_BYTE_GAS * nonzero_bytes;
+
+    let mut execution_gas: u64 = 0;
+    let mut data_dependent = false;
+    let mut max_word: u128 = 0;
+    // Literal operands pushed since the last non-`PUSH` opcode, nearest
+    // push last (i.e. top of stack last), so we can recover the arguments
+    // of a memory op that consumes a run of immediately preceding literals.
+    let mut pushed: Vec<Option<u128>> = Vec::new();
+
+    for token in stream {
+        match token {
+            Token::Opcode(op) => {
+                let name = op.name.to_ascii_uppercase();
+
+                if touches_memory(&name) {
+                    match memory_footprint(&name, &pushed) {
+                        Some((offset, size)) => {
+                            max_word = max_word.max(words_touched(offset, size));
+                            execution_gas += per_word_surcharge(&name, size);
+                        }
+                        None => data_dependent = true,
+                    }
+                }
+
+                let cost = static_cost(&name);
+                execution_gas += cost.gas;
+                data_dependent |= cost.dynamic;
+
+                if !name.starts_with("PUSH") {
+                    pushed.clear();
+                }
+            }
+            Token::Constant(c) => pushed.push(u128::from_str_radix(c, 16).ok()),
+            Token::Operator(_) | Token::Builtin(_) | Token::LabelBegin(_) | Token::LabelEnd => {
+                // Not resolvable statically without first running `labelize`;
+                // contributes neither gas nor calldata bytes here.
+            }
+        }
+    }
+
+    execution_gas += memory_expansion_cost(max_word);
+
+    GasEstimate {
+        intrinsic_gas,
+        execution_gas,
+        data_dependent,
+    }
+}
+
+fn count_calldata_bytes(stream: &[Token]) -> (u64, u64) {
+    let mut zero = 0;
+    let mut nonzero = 0;
+    for token in stream {
+        if let Ok(bytes) = token.bytecode() {
+            for byte in bytes {
+                if byte == 0 {
+                    zero += 1;
+                } else {
+                    nonzero += 1;
+                }
+            }
+        }
+    }
+    (zero, nonzero)
+}
+
+/// `3*words + words^2/512`, the standard EVM memory-expansion cost to grow
+/// active memory to `words` 32-byte words.
+fn memory_expansion_cost(words: u128) -> u64 {
+    let cost = 3 * words + words.saturating_mul(words) / 512;
+    cost.min(u64::MAX as u128) as u64
+}
+
+fn words_touched(offset: u128, size: u128) -> u128 {
+    if size == 0 {
+        return 0;
+    }
+    offset.saturating_add(size).saturating_add(31) / 32
+}
+
+/// `SHA3` charges 6 gas per 32-byte word hashed, and the `CODECOPY`/
+/// `CALLDATACOPY`/`RETURNDATACOPY` family charges 3 gas per word copied, on
+/// top of their flat [`static_cost`] and the one-time [`memory_expansion_cost`]
+/// memory growth already accounts for.
+fn per_word_surcharge(name: &str, size: u128) -> u64 {
+    let per_word = match name {
+        "SHA3" => 6,
+        "CODECOPY" | "CALLDATACOPY" | "RETURNDATACOPY" => 3,
+        _ => return 0,
+    };
+
+    let words = size.saturating_add(31) / 32;
+    words.saturating_mul(per_word).min(u64::MAX as u128) as u64
+}
+
+fn touches_memory(name: &str) -> bool {
+    matches!(
+        name,
+        "MSTORE"
+            | "MLOAD"
+            | "MSTORE8"
+            | "SHA3"
+            | "RETURN"
+            | "REVERT"
+            | "LOG0"
+            | "LOG1"
+            | "LOG2"
+            | "LOG3"
+            | "LOG4"
+            | "CODECOPY"
+            | "CALLDATACOPY"
+            | "RETURNDATACOPY"
+    )
+}
+
+/// Recovers `(offset, size)` of the memory region `name` touches, reading
+/// backwards from the literals in `pushed` (nearest push first). Returns
+/// `None` if any of the needed operands weren't literal constants pushed
+/// directly before this opcode.
+fn memory_footprint(name: &str, pushed: &[Option<u128>]) -> Option<(u128, u128)> {
+    let mut nearest = pushed.iter().rev().copied();
+    match name {
+        "MSTORE" | "MLOAD" => Some((nearest.next()??, 32)),
+        "MSTORE8" => Some((nearest.next()??, 1)),
+        "SHA3" | "RETURN" | "REVERT" | "LOG0" | "LOG1" | "LOG2" | "LOG3" | "LOG4" => {
+            let offset = nearest.next()??;
+            let size = nearest.next()??;
+            Some((offset, size))
+        }
+        "CODECOPY" | "CALLDATACOPY" | "RETURNDATACOPY" => {
+            let dest = nearest.next()??;
+            let _offset = nearest.next()??;
+            let size = nearest.next()??;
+            Some((dest, size))
+        }
+        _ => None,
+    }
+}
+
+struct OpcodeCost {
+    gas: u64,
+    /// True cost depends on runtime state this static pass can't see.
+    dynamic: bool,
+}
+
+impl OpcodeCost {
+    const fn fixed(gas: u64) -> Self {
+        Self { gas, dynamic: false }
+    }
+
+    const fn dynamic(gas: u64) -> Self {
+        Self { gas, dynamic: true }
+    }
+}
+
+/// The standard EVM per-opcode static gas cost, reporting the cheapest
+/// case (plus [`OpcodeCost::dynamic`]) for opcodes whose true cost depends
+/// on runtime state.
+fn static_cost(name: &str) -> OpcodeCost {
+    match name {
+        "STOP" | "RETURN" | "REVERT" | "INVALID" => OpcodeCost::fixed(0),
+        "ADD" | "SUB" | "NOT" | "LT" | "GT" | "SLT" | "SGT" | "EQ" | "ISZERO" | "AND" | "OR"
+        | "XOR" | "BYTE" | "SHL" | "SHR" | "SAR" | "PUSH0" | "CALLDATALOAD" => OpcodeCost::fixed(3),
+        "MUL" | "DIV" | "SDIV" | "MOD" | "SMOD" | "SIGNEXTEND" => OpcodeCost::fixed(5),
+        "ADDMOD" | "MULMOD" | "JUMP" => OpcodeCost::fixed(8),
+        "JUMPI" => OpcodeCost::fixed(10),
+        "JUMPDEST" => OpcodeCost::fixed(1),
+        "POP" => OpcodeCost::fixed(2),
+        "MLOAD" | "MSTORE" | "MSTORE8" => OpcodeCost::fixed(3),
+        "CALLDATACOPY" | "CODECOPY" | "RETURNDATACOPY" => OpcodeCost::fixed(3),
+        "SHA3" => OpcodeCost::fixed(30),
+        // Cheapest of the SSTORE family (warm reset, 5000); the 20000 cold
+        // init cost and the up-to-15000 refund both depend on the storage
+        // slot's current value, which we can't see statically.
+        "SSTORE" => OpcodeCost::dynamic(5_000),
+        "SLOAD" => OpcodeCost::fixed(100),
+        "BALANCE" | "EXTCODESIZE" | "EXTCODEHASH" | "EXTCODECOPY" => OpcodeCost::fixed(100),
+        "ADDRESS" | "ORIGIN" | "CALLER" | "CALLVALUE" | "CALLDATASIZE" | "CODESIZE"
+        | "GASPRICE" | "COINBASE" | "TIMESTAMP" | "NUMBER" | "DIFFICULTY" | "GASLIMIT"
+        | "CHAINID" | "SELFBALANCE" | "BASEFEE" | "PC" | "MSIZE" | "GAS" | "RETURNDATASIZE" => {
+            OpcodeCost::fixed(2)
+        }
+        "BLOCKHASH" => OpcodeCost::fixed(20),
+        "LOG0" => OpcodeCost::dynamic(375),
+        "LOG1" => OpcodeCost::dynamic(375 + 375),
+        "LOG2" => OpcodeCost::dynamic(375 + 2 * 375),
+        "LOG3" => OpcodeCost::dynamic(375 + 3 * 375),
+        "LOG4" => OpcodeCost::dynamic(375 + 4 * 375),
+        "CREATE" => OpcodeCost::fixed(32_000),
+        "CREATE2" => OpcodeCost::fixed(32_000),
+        // Base warm-account cost; +9000 if value is transferred, +25000 if
+        // it targets a nonexistent account -- both data-dependent.
+        "CALL" | "CALLCODE" | "DELEGATECALL" | "STATICCALL" => OpcodeCost::dynamic(100),
+        "SELFDESTRUCT" => OpcodeCost::dynamic(5_000),
+        name if name.starts_with("PUSH") => OpcodeCost::fixed(3),
+        name if name.starts_with("DUP") || name.starts_with("SWAP") => OpcodeCost::fixed(3),
+        // `GDEFAULT`: a conservative floor for anything not listed above.
+        _ => OpcodeCost::fixed(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::{opcode, tokenizer::push_constant};
+
+    use super::{estimate_gas, Token};
+
+    macro_rules! op {
+        ($op: literal) => {
+            Token::opcode(opcode($op).unwrap())
+        };
+    }
+
+    #[test]
+    fn sums_static_opcode_costs() {
+        // push1 0x01, push1 0x02, add, stop: 3 + 3 + 3 = 9 gas, no memory
+        // touched, nothing data-dependent.
+        let mut stream = vec![];
+        stream.extend(push_constant("01"));
+        stream.extend(push_constant("02"));
+        stream.push(op!("add"));
+        stream.push(op!("stop"));
+
+        let estimate = estimate_gas(&stream);
+        assert_eq!(estimate.execution_gas, 9);
+        assert!(!estimate.data_dependent);
+    }
+
+    #[test]
+    fn flags_data_dependent_opcodes() {
+        let stream = vec![op!("sload"), op!("push0"), op!("sstore")];
+        let estimate = estimate_gas(&stream);
+        assert!(estimate.data_dependent);
+    }
+
+    #[test]
+    fn accounts_for_memory_expansion() {
+        // mstore(0x20, 0x01): writes word 1, so memory grows to 2 words.
+        let mut stream = vec![];
+        stream.extend(push_constant("01"));
+        stream.extend(push_constant("20"));
+        stream.push(op!("mstore"));
+
+        let estimate = estimate_gas(&stream);
+        // 3 (push) + 3 (push) + 3 (mstore) + memory_expansion_cost(2) = 9 + 6
+        assert_eq!(estimate.execution_gas, 9 + (3 * 2 + 4 / 512));
+        assert!(!estimate.data_dependent);
+    }
+
+    #[test]
+    fn charges_sha3_six_gas_per_word_hashed() {
+        // sha3(0x00, 0x40): hashes 2 words (0x40 = 64 bytes).
+        let mut stream = vec![];
+        stream.extend(push_constant("40"));
+        stream.extend(push_constant("00"));
+        stream.push(op!("sha3"));
+
+        let estimate = estimate_gas(&stream);
+        // 3 + 3 (pushes) + 30 (flat sha3) + 6*2 (per-word) + memory_expansion_cost(2)
+        assert_eq!(estimate.execution_gas, 3 + 3 + 30 + 12 + (3 * 2 + 4 / 512));
+        assert!(!estimate.data_dependent);
+    }
+
+    #[test]
+    fn charges_codecopy_three_gas_per_word_copied() {
+        // codecopy(0x00, 0x00, 0x40): copies 2 words (0x40 = 64 bytes).
+        let mut stream = vec![];
+        stream.extend(push_constant("40"));
+        stream.extend(push_constant("00"));
+        stream.extend(push_constant("00"));
+        stream.push(op!("codecopy"));
+
+        let estimate = estimate_gas(&stream);
+        // 3*3 (pushes) + 3 (flat codecopy) + 3*2 (per-word) + memory_expansion_cost(2)
+        assert_eq!(estimate.execution_gas, 9 + 3 + 6 + (3 * 2 + 4 / 512));
+        assert!(!estimate.data_dependent);
+    }
+}
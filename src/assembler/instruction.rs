@@ -0,0 +1,8 @@
+//! The EVM opcode table: `opcode(name)`, `instruction(byte)`, and
+//! `operand_size(byte)`.
+//!
+//! Generated at build time by `build.rs` from `instructions.in` at the crate
+//! root, so covering a new hardfork's opcodes (Shanghai's `PUSH0`, Cancun's
+//! `TLOAD`/`TSTORE`/`MCOPY`, and whatever comes next) is a one-line edit to
+//! that table instead of a hand-maintained match arm here.
+include!(concat!(env!("OUT_DIR"), "/opcode_table.rs"));
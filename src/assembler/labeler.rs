@@ -1,172 +1,173 @@
 use std::collections::HashMap;
 
-use crate::assembler::tokenizer::push_constant;
+use log::trace;
 
-use super::tokenizer::Token;
+use crate::assembler::opcode;
+
+use super::{error::AssembleError, tokenizer::Token};
+
+/// Safety cap: the EVM's widest PUSH operand is 32 bytes.
+const MAX_PUSH_WIDTH: usize = 32;
 
 struct Label {
-    name: String,
     index: usize,
     size: usize,
 }
 
-pub fn labelize(stream: &[Token]) -> Vec<Token> {
+/// Resolves `dataOffset`/`dataSize` labels and the `bytecodeSize` builtin
+/// into concrete `PUSH`es, each sized to the minimum number of bytes that
+/// can hold its value.
+///
+/// Every variable-width push starts at 1 byte. Because growing one push can
+/// shift label offsets past a `2^(8n)` boundary and force a downstream push
+/// to grow too, we lay out the stream, widen whichever pushes turned out too
+/// small for their resolved value, and repeat until a full pass changes
+/// nothing. Widths only ever grow, so this is guaranteed to converge.
+pub fn labelize(stream: &[Token]) -> Result<Vec<Token>, AssembleError> {
+    let mut widths = vec![1usize; stream.len()];
+
+    loop {
+        let (labels, bytecode_size) = layout(stream, &widths)?;
+
+        let mut changed = false;
+        for (i, token) in stream.iter().enumerate() {
+            let value = match token {
+                Token::Operator(operator) => resolve(operator, &labels)?,
+                Token::Builtin(_) => bytecode_size,
+                _ => continue,
+            };
+
+            let needed = min_width(value)?;
+            if needed > widths[i] {
+                widths[i] = needed;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return render(stream, &labels, bytecode_size, &widths);
+        }
+    }
+}
+
+fn resolve(
+    operator: &super::tokenizer::Operator,
+    labels: &HashMap<String, Label>,
+) -> Result<usize, AssembleError> {
+    let label = labels
+        .get(&operator.arg)
+        .ok_or_else(|| AssembleError::UndefinedLabel {
+            name: operator.arg.clone(),
+        })?;
+
+    Ok(match operator.name.as_ref() {
+        "dataOffset" => label.index,
+        "dataSize" => label.size,
+        _ => unreachable!(),
+    })
+}
+
+/// Walks `stream` assuming each variable-width push at position `i` occupies
+/// `1 + widths[i]` bytes (PUSH opcode + operand), returning every label's
+/// `(index, size)` plus the total bytecode size.
+fn layout(
+    stream: &[Token],
+    widths: &[usize],
+) -> Result<(HashMap<String, Label>, usize), AssembleError> {
+    struct OpenLabel {
+        name: String,
+        index: usize,
+    }
+
     let mut labels = HashMap::new();
-    let mut stack = Vec::new();
+    let mut stack: Vec<OpenLabel> = Vec::new();
 
     let mut index = 0;
-    let label_size = estimate_max_label_size(stream);
-    for token in stream {
+    for (i, token) in stream.iter().enumerate() {
+        trace!("{index:x}: {token:?}");
         match token {
             Token::LabelBegin(name) => {
-                stack.push(Label {
+                stack.push(OpenLabel {
                     name: name.clone(),
                     index,
-                    size: 0,
                 });
                 index = 0;
             }
             Token::LabelEnd => {
-                let label = stack.pop();
-                let Some(label) = label else {
-                    // TODO: Maybe make this fallible?
-                    panic!("Imbalanced labels at index {index}");
-                };
+                let label = stack
+                    .pop()
+                    .ok_or(AssembleError::ImbalancedLabels { index })?;
                 index += label.index;
                 labels.insert(
-                    label.name.clone(),
+                    label.name,
                     Label {
-                        name: label.name,
                         index: label.index,
                         size: index,
                     },
                 );
             }
             Token::Opcode(_) | Token::Constant(_) => index += token.size(),
-            Token::Operator(operator) if operator.name == "dataOffset" => {
-                index += 1; // A PUSH instruction.
-                index += label_size;
-            }
-            Token::Operator(_) => {
-                index += 1; // A PUSH instruction.
-                index += 32; // We can't know datasize here.
-            }
-            Token::Builtin(_) => {
-                index += 1; // A PUSH instruction.
-                index += token.size() - 1
-            }
+            Token::Operator(_) | Token::Builtin(_) => index += 1 + widths[i],
         }
     }
 
-    let bytecode = stream
-        .iter()
-        .filter(|t| !matches!(t, Token::LabelBegin(_) | Token::LabelEnd))
-        .flat_map(|t| match t {
-            Token::Operator(operator) => {
-                // TODO: Maybe make this fallible?
-                let label = labels
-                    .get(&operator.arg)
-                    .unwrap_or_else(|| panic!("Label '{}' not found", operator.arg));
-
-                let tokens = match operator.name.as_ref() {
-                    "dataOffset" => {
-                        let label_size = label_size + label_size % 2;
-                        let constant = &format!("{:0width$x}", label.index, width = label_size);
-                        push_constant(constant)
-                    }
-                    "dataSize" => {
-                        let constant = &format!("{:0width$x}", label.size, width = 64);
-                        push_constant(constant)
-                    }
-                    _ => unreachable!(),
-                };
+    if !stack.is_empty() {
+        return Err(AssembleError::ImbalancedLabels { index });
+    }
 
-                tokens
-            }
-            Token::Builtin(_) => vec![t.clone()],
-            Token::Opcode(_) | Token::Constant(_) => vec![t.clone()],
-            Token::LabelBegin(_) | Token::LabelEnd => unreachable!(),
-        })
-        .collect::<Vec<_>>();
+    Ok((labels, index))
+}
 
-    let bytecode_size: usize = stream
-        .iter()
-        .map(|t| match t {
-            Token::Opcode(_) | Token::Constant(_) | Token::Builtin(_) => t.size(),
-            Token::Operator(operator) => {
-                // TODO: Maybe make this fallible?
-                let label = labels
-                    .get(&operator.arg)
-                    .unwrap_or_else(|| panic!("Label '{}' not found", operator.arg));
+/// Minimum number of bytes needed to represent `value`, from 1 up to
+/// [`MAX_PUSH_WIDTH`]. Errors rather than truncating if `value` doesn't fit
+/// in a single EVM word.
+fn min_width(value: usize) -> Result<usize, AssembleError> {
+    let bits = usize::BITS - value.leading_zeros();
+    let width = (bits as usize).div_ceil(8).max(1);
 
-                let size = match operator.name.as_ref() {
-                    "dataOffset" => {
-                        let label_size = label_size + label_size % 2;
-                        let constant = &format!("{:0width$x}", label.index, width = label_size);
-                        1 + constant.len() / 2
-                    }
-                    "dataSize" => {
-                        let constant = &format!("{:0width$x}", label.size, width = 64);
-                        1 + constant.len() / 2
-                    }
-                    _ => unreachable!(),
-                };
+    if width > MAX_PUSH_WIDTH {
+        return Err(AssembleError::LabelTooLarge);
+    }
 
-                size
-            }
-            Token::LabelBegin(_) | Token::LabelEnd => 0,
-        })
-        .sum();
+    Ok(width)
+}
 
-    bytecode
-        .into_iter()
-        .flat_map(|t| match t {
+/// Substitutes every resolved operator/builtin with its minimal-width
+/// `PUSH`, dropping the now-redundant label markers.
+fn render(
+    stream: &[Token],
+    labels: &HashMap<String, Label>,
+    bytecode_size: usize,
+    widths: &[usize],
+) -> Result<Vec<Token>, AssembleError> {
+    stream
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| !matches!(t, Token::LabelBegin(_) | Token::LabelEnd))
+        .map(|(i, t)| match t {
+            Token::Operator(operator) => Ok(push_with_width(resolve(operator, labels)?, widths[i])),
+            Token::Builtin(_) => Ok(push_with_width(bytecode_size, widths[i])),
+            Token::Opcode(_) | Token::Constant(_) => Ok(vec![t.clone()]),
             Token::LabelBegin(_) | Token::LabelEnd => unreachable!(),
-            // TODO: Compute size properly instead of using 32 bytes.
-            Token::Builtin(_) => {
-                let constant = &format!("{:0width$x}", bytecode_size, width = 64);
-                push_constant(constant)
-            }
-            Token::Opcode(_) | Token::Constant(_) | Token::Operator(_) => vec![t],
         })
-        .collect()
+        .collect::<Result<Vec<_>, AssembleError>>()
+        .map(|tokens| tokens.concat())
 }
 
-/// Estimates the maximum label size.
-///
-/// That is, how many hex digits do we need to represent all the addressable
-/// contract offsets, including labels.
-fn estimate_max_label_size(stream: &[Token]) -> usize {
-    let contract_size_without_labels: usize = stream.iter().map(|t| t.size()).sum();
-    let label_count = stream
-        .iter()
-        .filter(|t| matches!(t, Token::Operator(_)))
-        .count();
-    // Tbh, impossible to reach...
-    let max_label_size: usize = 64;
-    // We are looking for the number of hex digits such that the contract size
-    // "fits" in.
-    let mut hex_digits = 2;
-    while hex_digits < max_label_size {
-        let contract_size: usize =
-            contract_size_without_labels + (1 + hex_digits / 2) * label_count;
-
-        if 16_usize.pow(hex_digits as u32) >= contract_size {
-            return hex_digits / 2;
-        }
-
-        hex_digits += 1;
-    }
-
-    max_label_size / 2
+/// A `PUSH{width}` of `value`, zero-padded to exactly `width` operand bytes
+/// so it occupies the `1 + width` bytes [`layout`] reserved for it, even
+/// when `value` is zero (where `push_constant` would emit a 1-byte `PUSH0`).
+fn push_with_width(value: usize, width: usize) -> Vec<Token> {
+    let push = opcode(&format!("PUSH{width}")).expect("width is at most 32 bytes");
+    let constant = format!("{:0digits$x}", value, digits = width * 2);
+    vec![Token::opcode(push), Token::constant(&constant)]
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::assembler::tokenizer::Token;
     use crate::assembler::{opcode, tokenizer::Operator};
 
-    use super::estimate_max_label_size;
+    use super::{labelize, min_width, Token};
 
     macro_rules! op {
         ($op: literal) => {
@@ -174,129 +175,35 @@ mod tests {
         };
     }
 
-    macro_rules! constant {
-        ($c: literal) => {
-            Token::Constant($c.to_owned())
-        };
-    }
-
-    macro_rules! label_begin {
-        ($c: literal) => {
-            Token::LabelBegin($c.to_owned())
-        };
-    }
-
     #[test]
-    fn estimates_max_label_size() {
+    fn minimizes_push_width() {
+        // `dataOffset(tag_1)` resolves to 4 (jumpdest, push1 + operand,
+        // jumpdest come before it), small enough for a single byte, so it
+        // should assemble down to a `PUSH1`, not the 32-byte operand the old
+        // hardcoded-width approach produced.
         let stream = [
-            op!("push1"),
-            constant!("80"),
-            op!("push1"),
-            constant!("40"),
-            op!("mstore"),
-            op!("callvalue"),
-            op!("dup1"),
-            op!("iszero"),
-            Token::Operator(Operator {
-                name: "dataOffset".to_owned(),
-                arg: "tag_1".to_owned(),
-            }),
-            op!("jumpi"),
-            op!("push0"),
-            op!("dup1"),
-            op!("revert"),
-            label_begin!("tag_1"),
-            Token::LabelEnd,
-            op!("jumpdest"),
-            op!("pop"),
-            op!("push1"),
-            constant!("40"),
-            op!("mload"),
-            Token::Builtin("bytecodeSize".to_owned()),
-            op!("codesize"),
-            op!("sub"),
-            op!("dup1"),
-            Token::Builtin("bytecodeSize".to_owned()),
-            op!("dup4"),
-            op!("codecopy"),
-            op!("dup2"),
-            op!("add"),
-            op!("push1"),
-            constant!("40"),
-            op!("dup2"),
-            op!("swap1"),
-            op!("mstore"),
-            Token::Operator(Operator {
-                name: "dataOffset".to_owned(),
-                arg: "tag_2".to_owned(),
-            }),
-            op!("swap2"),
-            Token::Operator(Operator {
-                name: "dataOffset".to_owned(),
-                arg: "tag_3".to_owned(),
-            }),
-            op!("jump"),
-            label_begin!("tag_2"),
-            Token::LabelEnd,
             op!("jumpdest"),
-            op!("push0"),
-            op!("sstore"),
             Token::Operator(Operator {
                 name: "dataOffset".to_owned(),
-                arg: "tag_7".to_owned(),
-            }),
-            op!("jump"),
-            label_begin!("tag_3"),
-            Token::LabelEnd,
-            op!("jumpdest"),
-            op!("push0"),
-            op!("push1"),
-            constant!("20"),
-            op!("dup3"),
-            op!("dup5"),
-            op!("sub"),
-            op!("slt"),
-            op!("iszero"),
-            Token::Operator(Operator {
-                name: "dataOffset".to_owned(),
-                arg: "tag_9".to_owned(),
+                arg: "tag_1".to_owned(),
             }),
-            op!("jumpi"),
-            op!("push0"),
-            op!("dup1"),
-            op!("revert"),
-            label_begin!("tag_9"),
-            Token::LabelEnd,
             op!("jumpdest"),
-            op!("pop"),
-            op!("mload"),
-            op!("swap2"),
-            op!("swap1"),
-            op!("pop"),
-            op!("jump"),
-            label_begin!("tag_7"),
+            Token::LabelBegin("tag_1".to_owned()),
             Token::LabelEnd,
             op!("jumpdest"),
-            Token::Operator(Operator {
-                name: "dataSize".to_owned(),
-                arg: "sub_0".to_owned(),
-            }),
-            op!("dup1"),
-            Token::Operator(Operator {
-                name: "dataOffset".to_owned(),
-                arg: "sub_0".to_owned(),
-            }),
-            op!("push0"),
-            op!("codecopy"),
-            op!("push0"),
-            op!("return"),
-            op!("stop"),
-            label_begin!("sub_0"),
-            constant!("eff00000"),
-            Token::LabelEnd,
         ];
 
-        let max_size = estimate_max_label_size(&stream);
-        assert_eq!(max_size, 1);
+        let resolved = labelize(&stream).unwrap();
+        assert_eq!(resolved[1], op!("push1"));
+        assert_eq!(resolved[2], Token::Constant("04".to_owned()));
+    }
+
+    #[test]
+    fn computes_min_width() {
+        assert_eq!(min_width(0).unwrap(), 1);
+        assert_eq!(min_width(0xff).unwrap(), 1);
+        assert_eq!(min_width(0x100).unwrap(), 2);
+        assert_eq!(min_width(0xffff).unwrap(), 2);
+        assert_eq!(min_width(0x10000).unwrap(), 3);
     }
 }
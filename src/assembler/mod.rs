@@ -1,7 +1,16 @@
 mod compile;
+mod diagnostics;
+mod error;
+mod gas;
 mod instruction;
 mod labeler;
+mod stack;
 mod tokenizer;
 
 pub use compile::assemble;
-pub use instruction::{instruction, opcode};
+pub use diagnostics::{Diagnostic, Severity, Span};
+pub use error::AssembleError;
+pub use gas::{estimate_gas, GasEstimate};
+pub use instruction::{instruction, opcode, operand_size};
+pub use stack::{verify_stack_balance, StackDiagnostic};
+pub use tokenizer::{tokenize_checked, Opcode, Operator, Token};
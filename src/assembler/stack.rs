@@ -0,0 +1,261 @@
+use std::fmt::Display;
+
+use super::{tokenizer::Token, Severity};
+
+/// A stack-balance problem found while statically walking a token stream,
+/// identified by the index of the offending token.
+///
+/// Token streams aren't tied back to source spans the way [`Diagnostic`]s
+/// from the tokenizer are, so this points at a position in the stream
+/// instead.
+///
+/// [`Diagnostic`]: super::Diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackDiagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub index: usize,
+}
+
+impl StackDiagnostic {
+    fn error(message: impl Into<String>, index: usize) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            index,
+        }
+    }
+
+    fn warning(message: impl Into<String>, index: usize) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            index,
+        }
+    }
+}
+
+/// Statically verifies `stream` never underflows the stack and doesn't
+/// obviously leak items across a fall-through, without running a real EVM.
+///
+/// Walks the stream maintaining a running stack height, split into basic
+/// blocks at `JUMPDEST`/[`Token::LabelBegin`] and after
+/// `JUMP`/`JUMPI`/`STOP`/`RETURN`/`REVERT`/`INVALID`/`SELFDESTRUCT`. Each
+/// block is conservatively assumed to start at height 0, since the actual
+/// incoming height depends on which jump reached it -- not knowable
+/// statically. A block that is entered with items already on the stack and
+/// consumes them is therefore reported as underflowing.
+/// [`Token::Operator`] and [`Token::Builtin`] are treated as pushing one
+/// word, since both resolve to a `PUSH` once labels are resolved.
+///
+/// The first underflow in a block is reported as an error and ends analysis
+/// of that block (its stack state can no longer be trusted); a block that
+/// falls through to the next one with a nonzero residual height is reported
+/// as a warning, since that's almost always a forgotten `pop` or a stray
+/// literal.
+pub fn verify_stack_balance(stream: &[Token]) -> Vec<StackDiagnostic> {
+    let mut diagnostics = vec![];
+    let mut height: i64 = 0;
+    let mut block_failed = false;
+
+    for (index, token) in stream.iter().enumerate() {
+        if starts_new_block(token) {
+            flag_residual_height(&mut diagnostics, height, index.saturating_sub(1));
+            height = 0;
+            block_failed = false;
+        }
+
+        if block_failed {
+            continue;
+        }
+
+        let (pops, pushes) = stack_effect(token);
+
+        if height < pops as i64 {
+            diagnostics.push(StackDiagnostic::error(
+                format!("stack underflow: {token} needs {pops} item(s) but only {height} are on the stack"),
+                index,
+            ));
+            block_failed = true;
+            continue;
+        }
+
+        height = height - pops as i64 + pushes as i64;
+
+        if ends_block(token) {
+            flag_residual_height(&mut diagnostics, height, index);
+            height = 0;
+            block_failed = false;
+        }
+    }
+
+    diagnostics
+}
+
+fn flag_residual_height(diagnostics: &mut Vec<StackDiagnostic>, height: i64, index: usize) {
+    if height > 0 {
+        diagnostics.push(StackDiagnostic::warning(
+            format!("block falls through with {height} unconsumed stack item(s)"),
+            index,
+        ));
+    }
+}
+
+fn starts_new_block(token: &Token) -> bool {
+    matches!(token, Token::LabelBegin(_))
+        || matches!(token, Token::Opcode(op) if op.name.eq_ignore_ascii_case("JUMPDEST"))
+}
+
+fn ends_block(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Opcode(op) if matches!(
+            op.name.to_ascii_uppercase().as_str(),
+            "JUMP" | "JUMPI" | "STOP" | "RETURN" | "REVERT" | "INVALID" | "SELFDESTRUCT"
+        )
+    )
+}
+
+/// `(pops, pushes)` for a single token. The operand byte(s) of a `PUSH`
+/// arrive as a separate `Token::Constant` right after its `Token::Opcode`,
+/// so they carry no stack effect of their own.
+fn stack_effect(token: &Token) -> (usize, usize) {
+    match token {
+        Token::Opcode(op) => opcode_stack_effect(&op.name.to_ascii_uppercase()),
+        Token::Constant(_) => (0, 0),
+        Token::Operator(_) | Token::Builtin(_) => (0, 1),
+        Token::LabelBegin(_) | Token::LabelEnd => (0, 0),
+    }
+}
+
+fn opcode_stack_effect(name: &str) -> (usize, usize) {
+    if let Some(n) = opcode_suffix(name, "DUP") {
+        return (n, n + 1);
+    }
+
+    if let Some(n) = opcode_suffix(name, "SWAP") {
+        return (n + 1, n + 1);
+    }
+
+    if opcode_suffix(name, "PUSH").is_some() {
+        return (0, 1);
+    }
+
+    if let Some(n) = opcode_suffix(name, "LOG") {
+        return (2 + n, 0);
+    }
+
+    match name {
+        "STOP" | "JUMPDEST" | "INVALID" => (0, 0),
+        "ADDRESS" | "ORIGIN" | "CALLER" | "CALLVALUE" | "CALLDATASIZE" | "CODESIZE"
+        | "GASPRICE" | "COINBASE" | "TIMESTAMP" | "NUMBER" | "DIFFICULTY" | "PREVRANDAO"
+        | "GASLIMIT" | "CHAINID" | "SELFBALANCE" | "BASEFEE" | "BLOBBASEFEE" | "PC" | "MSIZE"
+        | "GAS" | "RETURNDATASIZE" => (0, 1),
+        "ISZERO" | "NOT" | "BALANCE" | "CALLDATALOAD" | "EXTCODESIZE" | "EXTCODEHASH"
+        | "BLOCKHASH" | "MLOAD" | "SLOAD" | "TLOAD" | "BLOBHASH" => (1, 1),
+        "POP" | "JUMP" | "SELFDESTRUCT" => (1, 0),
+        "ADD" | "SUB" | "MUL" | "DIV" | "SDIV" | "MOD" | "SMOD" | "EXP" | "SIGNEXTEND" | "LT"
+        | "GT" | "SLT" | "SGT" | "EQ" | "AND" | "OR" | "XOR" | "BYTE" | "SHL" | "SHR" | "SAR"
+        | "SHA3" | "KECCAK256" => (2, 1),
+        "MSTORE" | "MSTORE8" | "SSTORE" | "TSTORE" | "JUMPI" | "RETURN" | "REVERT" => (2, 0),
+        "ADDMOD" | "MULMOD" | "CREATE" => (3, 1),
+        "CALLDATACOPY" | "CODECOPY" | "RETURNDATACOPY" | "MCOPY" => (3, 0),
+        "CREATE2" => (4, 1),
+        "EXTCODECOPY" => (4, 0),
+        "DELEGATECALL" | "STATICCALL" => (6, 1),
+        "CALL" | "CALLCODE" => (7, 1),
+        _ => (0, 0),
+    }
+}
+
+fn opcode_suffix(name: &str, prefix: &str) -> Option<usize> {
+    name.strip_prefix(prefix)?.parse().ok()
+}
+
+impl Display for StackDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} (token #{})",
+            self.severity, self.message, self.index
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::{opcode, tokenizer::push_constant, Severity};
+
+    use super::{verify_stack_balance, Token};
+
+    macro_rules! op {
+        ($op: literal) => {
+            Token::opcode(opcode($op).unwrap())
+        };
+    }
+
+    #[test]
+    fn accepts_balanced_straight_line_code() {
+        let mut stream = vec![];
+        stream.extend(push_constant("01"));
+        stream.extend(push_constant("02"));
+        stream.push(op!("add"));
+        stream.push(op!("pop"));
+        stream.push(op!("stop"));
+
+        assert!(verify_stack_balance(&stream).is_empty());
+    }
+
+    #[test]
+    fn reports_underflow() {
+        let stream = vec![op!("add")];
+        let diagnostics = verify_stack_balance(&stream);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert_eq!(0, diagnostics[0].index);
+    }
+
+    #[test]
+    fn stops_checking_a_block_after_its_first_underflow() {
+        // `mul` underflows (nothing pushed yet); `add` right after it would
+        // too, but shouldn't be reported since the block's state is already
+        // untrustworthy past the first failure.
+        let stream = vec![op!("mul"), op!("add")];
+        let diagnostics = verify_stack_balance(&stream);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(0, diagnostics[0].index);
+    }
+
+    #[test]
+    fn flags_residual_height_on_fall_through() {
+        let mut stream = vec![];
+        stream.extend(push_constant("01")); // left dangling on the stack
+        stream.push(op!("stop"));
+
+        let diagnostics = verify_stack_balance(&stream);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+    }
+
+    #[test]
+    fn resets_height_at_jumpdest() {
+        // `stop` ends the first block and `jumpdest` starts a new one at
+        // height 0, so the `pop` right after it is flagged as an underflow
+        // of that new block.
+        let stream = vec![op!("stop"), op!("jumpdest"), op!("pop")];
+        let diagnostics = verify_stack_balance(&stream);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(2, diagnostics[0].index);
+    }
+
+    #[test]
+    fn dup_requires_enough_depth() {
+        let mut stream = vec![];
+        stream.extend(push_constant("01"));
+        stream.push(op!("dup2")); // needs 2 items, only 1 present
+
+        let diagnostics = verify_stack_balance(&stream);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+    }
+}
@@ -1,10 +1,13 @@
-use std::fmt::Display;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
-use eyre::bail;
 use once_cell::sync::Lazy;
-use regex::{Regex, RegexBuilder};
+use regex::{Captures, Regex, RegexBuilder};
+use sha3::{Digest, Keccak256};
 
-use super::{instruction, opcode};
+use super::{error::AssembleError, instruction, opcode, Diagnostic, Span};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Opcode {
@@ -54,12 +57,20 @@ impl Token {
         }
     }
 
-    pub fn bytecode(&self) -> eyre::Result<Vec<u8>> {
+    pub fn bytecode(&self) -> Result<Vec<u8>, AssembleError> {
         match self {
-            Token::Opcode(op) => hex::decode(&op.hex).map_err(|e| e.into()),
-            Token::Constant(c) => hex::decode(c).map_err(|e| e.into()),
+            Token::Opcode(op) => {
+                hex::decode(&op.hex).map_err(|_| AssembleError::UnexpectedToken {
+                    token: self.to_string(),
+                })
+            }
+            Token::Constant(c) => hex::decode(c).map_err(|_| AssembleError::UnexpectedToken {
+                token: self.to_string(),
+            }),
             Token::Operator(_) | Token::LabelBegin(_) | Token::LabelEnd | Token::Builtin(_) => {
-                bail!("unexpected token found when generating bytecode")
+                Err(AssembleError::UnexpectedToken {
+                    token: self.to_string(),
+                })
             }
         }
     }
@@ -102,6 +113,10 @@ pub fn tokenize_part(instruction: &str) -> Vec<Token> {
         return tokens;
     }
 
+    if let Some(tokens) = tokenize_hash_builtin(instruction) {
+        return tokens;
+    }
+
     if let Some(tokens) = tokenize_operator(instruction) {
         return tokens;
     }
@@ -217,20 +232,20 @@ fn tokenize_call(call: &str) -> Option<Vec<Token>> {
     Some(tokens)
 }
 
-fn tokenize_label(instruction: &str) -> Option<Vec<Token>> {
-    static SINGLE_LINE_LABEL: Lazy<Regex> = Lazy::new(|| {
-        RegexBuilder::new(r"^([a-z][a-z\d_]*):$")
-            .case_insensitive(true)
-            .build()
-            .unwrap()
-    });
-    static MULTI_LINE_LABEL: Lazy<Regex> = Lazy::new(|| {
-        RegexBuilder::new(r"^([a-z][a-z\d_]*):\s*assembly\s*\{$")
-            .case_insensitive(true)
-            .build()
-            .unwrap()
-    });
+static SINGLE_LINE_LABEL: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r"^([a-z][a-z\d_]*):$")
+        .case_insensitive(true)
+        .build()
+        .unwrap()
+});
+static MULTI_LINE_LABEL: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r"^([a-z][a-z\d_]*):\s*assembly\s*\{$")
+        .case_insensitive(true)
+        .build()
+        .unwrap()
+});
 
+fn tokenize_label(instruction: &str) -> Option<Vec<Token>> {
     if let Some(captures) = SINGLE_LINE_LABEL.captures(instruction) {
         return Some(vec![
             Token::LabelBegin(captures[1].to_owned()),
@@ -265,6 +280,41 @@ fn tokenize_builtin(instruction: &str) -> Option<Vec<Token>> {
     None
 }
 
+/// Compile-time hashing builtins: `selector("sig(...)")` resolves to the
+/// first four bytes of `keccak256` of the UTF-8 signature (a `PUSH4`), and
+/// `keccak256(0x..)` resolves to the full 32-byte digest of a constant (a
+/// `PUSH32`). Both are evaluated here, during tokenization, rather than
+/// emitted as runtime opcodes -- the argument is parsed literally, not
+/// tokenized as a nested call, so users can write human-readable dispatch
+/// logic instead of hand-hashing selectors.
+fn tokenize_hash_builtin(instruction: &str) -> Option<Vec<Token>> {
+    static SELECTOR: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^selector\("(.*)"\)$"#).unwrap());
+    static KECCAK256: Lazy<Regex> = Lazy::new(|| {
+        RegexBuilder::new(r"^keccak256\((0x[\da-f]+)\)$")
+            .case_insensitive(true)
+            .build()
+            .unwrap()
+    });
+
+    if let Some(captures) = SELECTOR.captures(instruction) {
+        let digest = keccak256(captures[1].as_bytes());
+        return Some(push_constant(&hex::encode(&digest[..4])));
+    }
+
+    if let Some(captures) = KECCAK256.captures(instruction) {
+        let bytes = hex::decode(&captures[1][2..]).ok()?;
+        return Some(push_constant(&hex::encode(keccak256(&bytes))));
+    }
+
+    None
+}
+
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
 pub fn amend(evmasm: &str, wasm: &[u8]) -> String {
     static AUXDATA_BLOCK: Lazy<Regex> =
         Lazy::new(|| Regex::new(r"([\S\s]*\n.*:.*assembly.*)\{[\S\s]*auxdata:[\S\s]*\}").unwrap());
@@ -279,17 +329,299 @@ auxdata: {wasm}
     asm.to_string()
 }
 
-pub fn clean_asm(evmasm: &str) -> Vec<String> {
+pub fn clean_asm(evmasm: &str) -> Result<Vec<String>, AssembleError> {
     let asm = remove_comments(evmasm);
     let asm = remove_space_around_symbols(&asm);
     let asm = reduce_spaces(&asm);
+    let asm = expand_macros(&asm)?;
 
     let instructions = asm
         .split(' ')
         .filter(|s| !s.is_empty())
         .map(|s| s.to_owned())
         .collect();
-    instructions
+    Ok(instructions)
+}
+
+/// Like [`clean_asm`], but keeps track of where each instruction came from.
+///
+/// Spans point into the *cleaned* text -- after comments are stripped and
+/// macros expanded -- rather than `evmasm` itself, since those two
+/// transformations don't preserve a 1:1 mapping back to the original source.
+fn clean_asm_with_spans(evmasm: &str) -> Result<Vec<(String, Span)>, AssembleError> {
+    let asm = remove_comments(evmasm);
+    let asm = remove_space_around_symbols(&asm);
+    let asm = reduce_spaces(&asm);
+    let asm = expand_macros(&asm)?;
+
+    let mut words = vec![];
+    let mut offset = 0;
+    for part in asm.split(' ') {
+        let start = offset;
+        offset += part.len();
+        if !part.is_empty() {
+            words.push((part.to_owned(), Span { start, end: offset }));
+        }
+        offset += 1; // Account for the separator `split` consumed.
+    }
+    Ok(words)
+}
+
+/// Tokenizes `src`, collecting a [`Diagnostic`] for anything that can't be
+/// turned into a [`Token`] instead of silently guessing (as `tokenize` does,
+/// treating any unrecognized instruction as a reference to a label that may
+/// not exist).
+///
+/// Function-call syntax (`mstore(0x40, 0x80)`) is only checked as a whole --
+/// once a word matches call syntax its arguments are tokenized the same way
+/// `tokenize` does, so a malformed literal nested inside one isn't
+/// individually diagnosed, just silently reinterpreted as before. Widening
+/// that would mean threading spans through `tokenize_call`'s recursion,
+/// which isn't worth it until a real case shows up.
+///
+/// Returns `Ok` only if no diagnostics -- errors or warnings -- were raised.
+pub fn tokenize_checked(src: &str) -> Result<Vec<Token>, Vec<Diagnostic>> {
+    let words = clean_asm_with_spans(src).map_err(|err| {
+        vec![Diagnostic::error(
+            err.to_string(),
+            Span {
+                start: 0,
+                end: src.len(),
+            },
+        )]
+    })?;
+    let declared_labels: HashSet<&str> = words
+        .iter()
+        .filter_map(|(word, _)| declared_label_name(word))
+        .collect();
+
+    let mut tokens = vec![];
+    let mut diagnostics = vec![];
+    let mut open_labels: Vec<Span> = vec![];
+
+    for (word, span) in &words {
+        match tokenize_word_checked(word, *span, &declared_labels) {
+            Ok(word_tokens) => {
+                for token in &word_tokens {
+                    match token {
+                        Token::LabelBegin(_) => open_labels.push(*span),
+                        Token::LabelEnd => {
+                            if open_labels.pop().is_none() {
+                                diagnostics.push(Diagnostic::error(
+                                    "unbalanced label: closing brace has no matching label",
+                                    *span,
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                tokens.extend(word_tokens);
+            }
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+
+    for span in open_labels {
+        diagnostics.push(Diagnostic::error("unbalanced label: never closed", span));
+    }
+
+    if diagnostics.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+/// The label name `word` declares, if it's a label definition (either form
+/// [`tokenize_label`] recognizes).
+fn declared_label_name(word: &str) -> Option<&str> {
+    if let Some(captures) = SINGLE_LINE_LABEL.captures(word) {
+        return Some(&word[captures.get(1)?.range()]);
+    }
+
+    if let Some(captures) = MULTI_LINE_LABEL.captures(word) {
+        return Some(&word[captures.get(1)?.range()]);
+    }
+
+    None
+}
+
+/// Like [`tokenize_part`], but reports a [`Diagnostic`] instead of falling
+/// back to treating `word` as a label reference when it isn't one.
+fn tokenize_word_checked(
+    word: &str,
+    span: Span,
+    declared_labels: &HashSet<&str>,
+) -> Result<Vec<Token>, Diagnostic> {
+    if let Some(result) = tokenize_constant_checked(word, span) {
+        return result;
+    }
+
+    if let Some(byte) = opcode(word) {
+        return Ok(vec![Token::opcode(byte)]);
+    }
+
+    if let Some(tokens) = tokenize_auxdata(word) {
+        return Ok(tokens);
+    }
+
+    if let Some(tokens) = tokenize_builtin(word) {
+        return Ok(tokens);
+    }
+
+    if let Some(tokens) = tokenize_hash_builtin(word) {
+        return Ok(tokens);
+    }
+
+    if let Some(tokens) = tokenize_operator(word) {
+        return Ok(tokens);
+    }
+
+    if let Some(tokens) = tokenize_call(word) {
+        return Ok(tokens);
+    }
+
+    if let Some(tokens) = tokenize_label(word) {
+        return Ok(tokens);
+    }
+
+    if declared_labels.contains(word) {
+        return Ok(vec![Token::Operator(Operator {
+            name: "dataOffset".to_owned(),
+            arg: word.to_owned(),
+        })]);
+    }
+
+    Err(Diagnostic::error(
+        format!("unknown mnemonic or undefined label reference '{word}'"),
+        span,
+    ))
+}
+
+/// If `word` looks like a hex literal constant (`0x...`), validates it and
+/// returns its tokens or an error diagnostic; returns `None` if `word` isn't
+/// a hex literal at all, so the caller can try the next kind of token.
+fn tokenize_constant_checked(word: &str, span: Span) -> Option<Result<Vec<Token>, Diagnostic>> {
+    static HEX_PREFIX: Lazy<Regex> = Lazy::new(|| {
+        RegexBuilder::new(r"^0x")
+            .case_insensitive(true)
+            .build()
+            .unwrap()
+    });
+
+    if !HEX_PREFIX.is_match(word) {
+        return None;
+    }
+
+    let digits = &word[2..];
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(Err(Diagnostic::error(
+            format!("malformed hex literal '{word}'"),
+            span,
+        )));
+    }
+
+    let bytes = digits.len().div_ceil(2);
+    if bytes > 32 {
+        return Some(Err(Diagnostic::error(
+            format!("constant '{word}' is {bytes} bytes, exceeding the 32-byte PUSH limit"),
+            span,
+        )));
+    }
+
+    Some(Ok(push_constant(digits)))
+}
+
+/// Maximum nesting depth for macro call expansion, guarding against a
+/// self-referential or mutually recursive macro definition looping forever.
+const MAX_MACRO_EXPANSION_DEPTH: usize = 32;
+
+/// Expands user-defined macros -- `macro name(params) { body }`, invoked as
+/// `name(args)` -- into their bodies, substituting call-site arguments for
+/// the macro's parameters. Call sites may reference other macros, expanded
+/// recursively up to [`MAX_MACRO_EXPANSION_DEPTH`]; anything left over once
+/// macros are expanded (plain opcode calls, `dataOffset`/`dataSize`, the
+/// hash builtins) is untouched here and handled as usual once `tokenize_part`
+/// sees it.
+fn expand_macros(asm: &str) -> Result<String, AssembleError> {
+    static MACRO_DEF: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"macro\s+([a-zA-Z_]\w*)\(([^)]*)\)\s*\{([^}]*)\}").unwrap());
+
+    let mut macros = HashMap::new();
+    let asm = MACRO_DEF.replace_all(asm, |captures: &Captures| {
+        let name = captures[1].to_owned();
+        let params = captures[2]
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        macros.insert(name, (params, captures[3].to_owned()));
+        String::new()
+    });
+
+    expand_macro_calls(&asm, &macros, 0)
+}
+
+/// Expands one round of macro calls and recurses until nothing changes.
+///
+/// Matching happens per whitespace-delimited word -- the same unit
+/// [`clean_asm`] eventually splits the stream into -- rather than across the
+/// whole joined text, and each word must be *entirely* `name(args)` with no
+/// nested parentheses. Otherwise a macro call regex run over raw text would
+/// also rewrite call-shaped text sitting inside unrelated constructs, e.g.
+/// `selector("transfer(address,uint256)")` would have its quoted signature
+/// corrupted if a `transfer` macro happened to exist.
+fn expand_macro_calls(
+    asm: &str,
+    macros: &HashMap<String, (Vec<String>, String)>,
+    depth: usize,
+) -> Result<String, AssembleError> {
+    static CALL: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([a-zA-Z_]\w*)\(([^()]*)\)$").unwrap());
+
+    if macros.is_empty() {
+        return Ok(asm.to_owned());
+    }
+
+    let mut expanded_any = false;
+    let expanded = asm
+        .split(' ')
+        .map(|word| {
+            let Some(captures) = CALL.captures(word) else {
+                return word.to_owned();
+            };
+            let Some((params, body)) = macros.get(&captures[1]) else {
+                return word.to_owned();
+            };
+
+            expanded_any = true;
+            let args = captures[2].split(',').map(str::trim).collect::<Vec<_>>();
+            substitute_macro_args(body, params, &args)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if !expanded_any {
+        return Ok(expanded);
+    }
+
+    if depth + 1 >= MAX_MACRO_EXPANSION_DEPTH {
+        return Err(AssembleError::MacroExpansionTooDeep {
+            max_depth: MAX_MACRO_EXPANSION_DEPTH,
+        });
+    }
+
+    expand_macro_calls(&expanded, macros, depth + 1)
+}
+
+fn substitute_macro_args(body: &str, params: &[String], args: &[&str]) -> String {
+    let mut body = body.to_owned();
+    for (param, arg) in params.iter().zip(args) {
+        let pattern = Regex::new(&format!(r"\b{}\b", regex::escape(param))).unwrap();
+        body = pattern.replace_all(&body, *arg).to_string();
+    }
+    body
 }
 
 fn remove_comments(asm: &str) -> String {
@@ -320,9 +652,11 @@ fn remove_space_around_symbols(asm: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::assembler::{opcode, tokenizer::Operator};
+    use crate::assembler::{opcode, tokenizer::Operator, AssembleError, Severity};
 
-    use super::{push_constant, reduce_spaces, remove_comments, tokenize, Token};
+    use super::{
+        clean_asm, push_constant, reduce_spaces, remove_comments, tokenize, tokenize_checked, Token,
+    };
 
     #[test]
     fn removes_comments() {
@@ -422,6 +756,59 @@ tag_3:"##;
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn expands_macros() {
+        let asm = "macro storeAt(slot, val) { val slot sstore } storeAt(0x00,0x2a)";
+        let expanded = super::expand_macros(&reduce_spaces(asm)).unwrap();
+        assert_eq!(expanded.trim(), "0x2a 0x00 sstore");
+
+        let stream = clean_asm(asm).unwrap();
+        let actual = tokenize(stream);
+        let mut expected = vec![];
+        expected.extend(push_constant("2a"));
+        expected.extend(push_constant("00"));
+        expected.push(Token::opcode(opcode("sstore").unwrap()));
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn macro_expansion_does_not_corrupt_a_same_named_selector_string() {
+        let asm = r#"macro transfer(a, b) { a b sstore } selector("transfer(address,uint256)")"#;
+        let stream = clean_asm(asm).unwrap();
+        let actual = tokenize(stream);
+        let digest = super::keccak256(b"transfer(address,uint256)");
+        let expected = push_constant(&hex::encode(&digest[..4]));
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn self_referential_macro_expansion_errors_instead_of_panicking() {
+        let asm = "macro loop(x) { loop(x) } loop(0x00)";
+        let err = clean_asm(asm).unwrap_err();
+        assert!(matches!(err, AssembleError::MacroExpansionTooDeep { .. }));
+    }
+
+    #[test]
+    fn tokenizes_hash_builtins() {
+        let stream = r#"selector("transfer(address,uint256)")"#
+            .split_whitespace()
+            .map(|t| t.to_owned())
+            .collect();
+        let actual = tokenize(stream);
+        let digest = super::keccak256(b"transfer(address,uint256)");
+        let expected = push_constant(&hex::encode(&digest[..4]));
+        assert_eq!(expected, actual);
+
+        let stream = "keccak256(0x2a)"
+            .split_whitespace()
+            .map(|t| t.to_owned())
+            .collect();
+        let actual = tokenize(stream);
+        let digest = super::keccak256(&[0x2a]);
+        let expected = push_constant(&hex::encode(digest));
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn tokenizes_auxdata() {
         let stream = "auxdata:0x1234"
@@ -547,4 +934,48 @@ tag_3:"##;
         expected.push(Token::opcode(opcode("codecopy").unwrap()));
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn checked_tokenizing_matches_the_lenient_path_on_valid_input() {
+        let asm = "tag_1:\npop\njump(tag_1)\n0x2a";
+        let checked = tokenize_checked(asm).expect("valid assembly should have no diagnostics");
+        let lenient = tokenize(clean_asm(asm).unwrap());
+        assert_eq!(lenient, checked);
+    }
+
+    #[test]
+    fn reports_unknown_mnemonics() {
+        let diagnostics = tokenize_checked("push1 frobnicate").unwrap_err();
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert!(diagnostics[0].message.contains("frobnicate"));
+    }
+
+    #[test]
+    fn reports_malformed_hex_literals() {
+        let diagnostics = tokenize_checked("0xzz").unwrap_err();
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert!(diagnostics[0].message.contains("malformed hex literal"));
+    }
+
+    #[test]
+    fn reports_oversized_push_constants() {
+        let oversized = format!("0x{}", "ab".repeat(33));
+        let diagnostics = tokenize_checked(&oversized).unwrap_err();
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert!(diagnostics[0].message.contains("32-byte PUSH limit"));
+    }
+
+    #[test]
+    fn reports_unbalanced_labels() {
+        let diagnostics = tokenize_checked("}").unwrap_err();
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].message.contains("unbalanced label"));
+
+        let diagnostics = tokenize_checked("sub_0:assembly{\ndup1\n").unwrap_err();
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].message.contains("unbalanced label"));
+    }
 }
@@ -1,22 +1,57 @@
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf, time::Duration};
 
-use clap::{command, Parser, Subcommand};
-use owo_colors::OwoColorize;
+use clap::{command, Parser, Subcommand, ValueEnum};
 use tokio::runtime::Builder;
 
-use crate::deploy;
+use crate::{deploy, error::KobaError};
+
+/// Output mode shared by `generate` and `deploy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored progress lines plus a plain-text final result.
+    Human,
+    /// A single structured JSON record on stdout, suitable for CI.
+    Json,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Human => write!(f, "human"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
 
 /// Main entrypoing to `koba`.
-pub fn run() -> eyre::Result<()> {
+pub fn run() -> Result<(), KobaError> {
     let config = Config::parse();
+    init_logging(config.verbose);
     config.command.run()
 }
 
+/// Wires up `log`'s output so `-v`/`-vvv` actually surfaces the `trace!`/
+/// `debug!` calls scattered through the assembler; with no flag passed,
+/// only warnings and errors are printed.
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Info,
+        2 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+    env_logger::Builder::new().filter_level(level).init();
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Config {
     #[clap(subcommand)]
     pub command: Commands,
+    /// Increase log verbosity; repeat for more detail, e.g. `-vvv` for the
+    /// assembler's per-token trace output.
+    #[arg(short = 'v', long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
 }
 
 #[derive(Debug, Subcommand)]
@@ -25,13 +60,16 @@ enum Commands {
     Generate(Generate),
     #[command(name = "deploy")]
     Deploy(Deploy),
+    #[command(name = "disassemble")]
+    Disassemble(Disassemble),
 }
 
 impl Commands {
-    pub fn run(&self) -> eyre::Result<()> {
+    pub fn run(&self) -> Result<(), KobaError> {
         match self {
             Commands::Generate(command) => command.run(),
             Commands::Deploy(command) => command.run(),
+            Commands::Disassemble(command) => command.run(),
         }
     }
 }
@@ -45,12 +83,25 @@ pub struct Generate {
     /// Path to the contract's Solidity constructor code.
     #[arg(long)]
     pub sol: Option<PathBuf>,
-    /// ABI-encoded constructor arguments.
-    #[arg(long)]
+    /// ABI-encoded constructor arguments, as a single hex string.
+    #[arg(long, conflicts_with_all = ["constructor_signature", "constructor_arg"])]
     pub args: Option<String>,
+    /// Human-readable constructor signature, e.g.
+    /// `constructor(address,uint256,string)`, used together with
+    /// `--constructor-arg` instead of pre-encoding `--args` by hand.
+    #[arg(long)]
+    pub constructor_signature: Option<String>,
+    /// A constructor argument value, in order. Pass once per argument, e.g.
+    /// `--constructor-arg 0x123... --constructor-arg 42`.
+    #[arg(long = "constructor-arg")]
+    pub constructor_arg: Vec<String>,
     /// Whether to support the Stylus v1 testnet.
     #[arg(long)]
     pub legacy: bool,
+    /// Output format: human-readable text, or a single JSON record suitable
+    /// for machine consumption.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
 }
 
 const STYLUS_TESTNET_RPC: &str = "https://sepolia-rollup.arbitrum.io/rpc";
@@ -62,6 +113,10 @@ pub struct Deploy {
     pub generate_config: Generate,
     #[command(flatten)]
     pub auth: PrivateKey,
+    #[command(flatten)]
+    pub retry: RetryConfig,
+    #[command(flatten)]
+    pub confirm: ConfirmationConfig,
     /// Arbitrum RPC endpoint.
     #[arg(short = 'e', long, default_value = STYLUS_TESTNET_RPC)]
     pub endpoint: String,
@@ -71,20 +126,83 @@ pub struct Deploy {
     /// Whether to print progress messages during execution.
     #[arg(short = 'q', long, default_value_t = false)]
     pub quiet: bool,
+    /// Skip the preflight check that the node's Stylus version is one this
+    /// build of koba supports, deploying anyway.
+    #[arg(long)]
+    pub allow_version_mismatch: bool,
 }
 
-impl Deploy {
-    pub fn run(&self) -> eyre::Result<()> {
-        let runtime = Builder::new_multi_thread().enable_all().build()?;
-        let _address = runtime.block_on(deploy(self))?;
+/// Controls how flaky RPC calls (transport errors, timeouts, 429/5xx
+/// responses) are retried.
+#[derive(Parser, Debug, Clone)]
+pub struct RetryConfig {
+    /// Maximum number of attempts for a single RPC call before giving up.
+    #[arg(long, default_value_t = 5)]
+    pub max_attempts: u32,
+    /// Base backoff interval, in milliseconds. Doubled on every retry.
+    #[arg(long, default_value_t = 250)]
+    pub base_interval_ms: u64,
+    /// Maximum backoff interval, in milliseconds.
+    #[arg(long, default_value_t = 8_000)]
+    pub max_interval_ms: u64,
+}
 
-        if !self.quiet {
-            println!("{}", "success!".bright_green());
-        }
+impl RetryConfig {
+    /// Base backoff interval as a [`Duration`].
+    pub fn base_interval(&self) -> Duration {
+        Duration::from_millis(self.base_interval_ms)
+    }
+
+    /// Maximum backoff interval as a [`Duration`].
+    pub fn max_interval(&self) -> Duration {
+        Duration::from_millis(self.max_interval_ms)
+    }
+}
+
+/// Controls how koba waits for a submitted transaction to actually land,
+/// distinct from [`RetryConfig`]'s retries of individual RPC calls: this
+/// governs re-broadcasting the transaction itself (with a fresh nonce and
+/// gas price) if it never gets mined, and how many confirmations to wait
+/// for once it does.
+#[derive(Parser, Debug, Clone)]
+pub struct ConfirmationConfig {
+    /// Number of block confirmations to wait for after a transaction is
+    /// first mined before considering it final.
+    #[arg(long, default_value_t = 1)]
+    pub confirmations: u64,
+    /// Maximum number of times to re-broadcast a transaction, with a fresh
+    /// nonce and gas price, if it doesn't land within `--timeout`.
+    #[arg(long, default_value_t = 3)]
+    pub max_retries: u32,
+    /// Seconds to wait for a transaction receipt before re-broadcasting.
+    #[arg(long, default_value_t = 60)]
+    pub timeout: u64,
+}
+
+impl Deploy {
+    pub fn run(&self) -> Result<(), KobaError> {
+        let runtime = Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| KobaError::Other(e.into()))?;
+        runtime.block_on(deploy(self))?;
         Ok(())
     }
 }
 
+/// Disassemble raw EVM bytecode back into readable assembly.
+#[derive(Parser, Debug)]
+pub struct Disassemble {
+    /// Bytecode to disassemble, as a hex string (with or without a leading
+    /// `0x`).
+    #[arg(long)]
+    pub bytecode: String,
+    /// Output format: human-readable text, or a single JSON record suitable
+    /// for machine consumption.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
 #[derive(Parser, Debug)]
 #[group(required = true)]
 pub struct PrivateKey {
@@ -101,4 +219,37 @@ pub struct PrivateKey {
     /// Keystore password file.
     #[arg(long)]
     pub keystore_password_path: Option<PathBuf>,
+    /// BIP-39 mnemonic seed phrase. Warning: this exposes your seed phrase
+    /// to shell history.
+    #[arg(long)]
+    pub mnemonic: Option<String>,
+    /// File path to a text file containing a BIP-39 mnemonic seed phrase.
+    #[arg(long)]
+    pub mnemonic_path: Option<PathBuf>,
+    /// Optional BIP-39 passphrase (the "25th word") protecting the
+    /// mnemonic above.
+    #[arg(long)]
+    pub mnemonic_passphrase: Option<String>,
+    /// BIP-32 derivation path used to derive the signing key from the
+    /// mnemonic above. Defaults to `m/44'/60'/0'/0/{account-index}`.
+    #[arg(long)]
+    pub derivation_path: Option<String>,
+    /// Account index appended to the default derivation path. Ignored if
+    /// `--derivation-path` is set explicitly. Also used as the account index
+    /// for `--ledger`/`--trezor`.
+    #[arg(long, default_value_t = 0)]
+    pub account_index: u32,
+    /// Sign using a Ledger hardware wallet instead of a local key. Uses
+    /// `--derivation-path`/`--account-index` to pick the signing account.
+    #[arg(long)]
+    pub ledger: bool,
+    /// Sign using a Trezor hardware wallet instead of a local key. Uses
+    /// `--derivation-path`/`--account-index` to pick the signing account.
+    #[arg(long)]
+    pub trezor: bool,
+    /// Base URL of a remote signer that holds the private key and signs
+    /// transaction hashes over HTTP on koba's behalf, so the key never
+    /// touches this machine.
+    #[arg(long)]
+    pub remote_signer_url: Option<String>,
 }
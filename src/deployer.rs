@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use alloy::{
     hex::FromHex,
     network::{EthereumWallet, ReceiptResponse, TransactionBuilder},
@@ -12,13 +14,16 @@ use alloy::{
     transports::Transport,
 };
 use alloy::rpc::types::TransactionReceipt;
-use eyre::{bail, Context, ContextCompat, OptionExt};
 use owo_colors::OwoColorize;
+use tokio::time::{sleep, timeout};
 
 use crate::{
-    config::Deploy,
+    config::{Deploy, RetryConfig},
     constants::ARB_WASM_ADDRESS,
+    error::KobaError,
     formatting::{format_data_fee, format_file_size, format_gas},
+    reporter::{DeployReport, Reporter},
+    retry::with_retry,
     wasm,
 };
 
@@ -30,6 +35,8 @@ sol! {
             payable
             returns (uint16 version, uint256 dataFee);
 
+        function stylusVersion() external view returns (uint16 version);
+
         error ProgramNotWasm();
         error ProgramNotActivated();
         error ProgramNeedsUpgrade(uint16 version, uint16 stylusVersion);
@@ -45,73 +52,126 @@ pub enum Status {
     Activated,
 }
 
+/// The range of `ArbWasm` Stylus versions (inclusive) this build of koba was
+/// tested against.
+const SUPPORTED_STYLUS_VERSIONS: (u16, u16) = (1, 2);
+
+/// Fails fast if the node's Stylus version falls outside of
+/// [`SUPPORTED_STYLUS_VERSIONS`], unless `allow_mismatch` is set, in which
+/// case a warning is printed instead.
+async fn check_stylus_version<P, T>(
+    provider: &P,
+    allow_mismatch: bool,
+    reporter: &Reporter,
+) -> Result<(), KobaError>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    let arb_wasm = ArbWasm::new(ARB_WASM_ADDRESS, provider);
+    let ArbWasm::stylusVersionReturn { version } = arb_wasm
+        .stylusVersion()
+        .call()
+        .await
+        .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
+
+    let (min, max) = SUPPORTED_STYLUS_VERSIONS;
+    if version < min || version > max {
+        if !allow_mismatch {
+            return Err(KobaError::UnsupportedStylusVersion {
+                node: version,
+                min,
+                max,
+            });
+        }
+
+        reporter.status(
+            format!(
+                "warning: node runs Stylus version {version}, outside of the \
+                 supported range {min}-{max}; continuing anyway"
+            )
+            .yellow(),
+        );
+    }
+
+    Ok(())
+}
+
 fn get_data_fee(fee: U256) -> U256 {
     // Give some leeway so that activation doesn't fail -- it'll get refunded
     // anyways.
     fee * U256::from(120) / U256::from(100)
 }
 
-pub async fn deploy(config: &Deploy) -> eyre::Result<TransactionReceipt> {
-    let signer = config.auth.wallet()?;
+pub async fn deploy(config: &Deploy) -> Result<TransactionReceipt, KobaError> {
+    let reporter = Reporter::new(config.generate_config.format, config.quiet);
+
+    let signer = config.auth.wallet().await?;
     let sender = signer.address();
 
-    let rpc_url = config.endpoint.parse()?;
+    let rpc_url = config
+        .endpoint
+        .parse()
+        .map_err(|e| KobaError::Other(eyre::eyre!(e)))?;
     let provider = ProviderBuilder::new()
         .with_recommended_fillers()
         .wallet(EthereumWallet::from(signer))
         .on_http(rpc_url);
 
+    check_stylus_version(&provider, config.allow_version_mismatch, &reporter).await?;
+
     let wasm_path = &config.generate_config.wasm;
     let legacy = config.generate_config.legacy;
-    let runtime = wasm::compress(wasm_path, legacy).wrap_err("failed to compress wasm")?;
+    let runtime = wasm::compress(wasm_path, legacy)?;
 
-    let status = get_activation_fee(&runtime, &provider, sender).await?;
+    let status = get_activation_fee(&runtime, &provider, sender, &config.retry).await?;
     if let Status::Created(fee) = status {
-        if !config.quiet {
-            println!("{:?}", fee);
-        }
+        reporter.status(format!("{:?}", fee));
     }
 
+    let mut data_fee_wei = None;
     if !config.deploy_only {
         if let Status::Created(fee) = status {
+            data_fee_wei = Some(fee.to_string());
             let data_fee = get_data_fee(fee);
             let visual_fee = format_data_fee(fee).unwrap_or("???".red().to_string());
-            if !config.quiet {
-                println!("wasm data fee: {}", visual_fee);
-            }
+            reporter.status(format!("wasm data fee: {}", visual_fee));
 
-            let balance = provider.get_balance(sender).await?;
+            let balance = with_retry(&config.retry, || provider.get_balance(sender))
+                .await
+                .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
             if balance < data_fee {
-                bail!(
-                    "not enough funds in account {} to pay for data fee\n\
-                 balance {} < {}\n",
-                    sender.red(),
-                    balance.red(),
-                    format!("{data_fee} wei").red(),
-                );
+                return Err(KobaError::InsufficientFunds {
+                    have: balance,
+                    want: data_fee,
+                });
             }
         }
     }
 
-    let asm = crate::generate(&config.generate_config)?;
-    if !config.quiet {
-        println!("init code size: {}", format_file_size(asm.len(), 20, 28));
-        println!("deploying to RPC: {}", &config.endpoint.bright_magenta());
+    let (asm, warnings, gas_estimate) = crate::generate(&config.generate_config)?;
+    for warning in &warnings {
+        reporter.status(format!("{warning}"));
     }
+    reporter.status(format!("init code size: {}", format_file_size(asm.len(), 20, 28)));
+    reporter.status(format!("estimated gas: {}", format_gas(U256::from(gas_estimate.total()))));
+    reporter.status(format!("deploying to RPC: {}", &config.endpoint.bright_magenta()));
 
     let tx = TransactionRequest::default().into_create().with_input(asm);
-    let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
-    let program = receipt
-        .contract_address()
-        .wrap_err("failed to read contract address from tx receipt")?;
-
-    if !config.quiet {
-        println!("deployed code: {}", program.bright_purple());
-        println!(
-            "deployment tx hash: {}",
-            receipt.transaction_hash.bright_magenta()
-        );
-    }
+    let receipt = send_and_confirm(&provider, sender, tx, config, &reporter, "deployment").await?;
+    let program = receipt.contract_address().ok_or_else(|| {
+        KobaError::Other(eyre::eyre!("failed to read contract address from tx receipt"))
+    })?;
+
+    reporter.status(format!("deployed code: {}", program.bright_purple()));
+    reporter.status(format!(
+        "deployment tx hash: {}",
+        receipt.transaction_hash.bright_magenta()
+    ));
+
+    let mut activated = matches!(status, Status::Activated);
+    let mut activation_tx_hash = None;
+    let mut gas_used = None;
 
     if !config.deploy_only {
         if let Status::Created(fee) = status {
@@ -125,37 +185,197 @@ pub async fn deploy(config: &Deploy) -> eyre::Result<TransactionReceipt> {
                 .with_input(tx_input)
                 .with_value(data_fee);
 
-            if is_activated(&tx, &provider, &Default::default()).await? {
-                if !config.quiet {
-                    println!("{}", "wasm already activated!".bright_green());
-                }
-                return Ok(receipt);
-            }
+            if is_activated(&tx, &provider, &Default::default(), &config.retry).await? {
+                reporter.status("wasm already activated!".bright_green());
+                activated = true;
+            } else {
+                reporter.status(format!("activating contract: {}", program));
+                let activation_receipt =
+                    send_and_confirm(&provider, sender, tx, config, &reporter, "activation")
+                        .await?;
+
+                let gas = format_gas(U256::from(activation_receipt.gas_used));
+                reporter.status(format!("activated with {gas}"));
+                reporter.status(format!(
+                    "activation tx hash: {}",
+                    activation_receipt.transaction_hash.bright_magenta()
+                ));
 
-            if !config.quiet {
-                println!("activating contract: {}", program);
+                activated = true;
+                gas_used = Some(activation_receipt.gas_used);
+                activation_tx_hash = Some(activation_receipt.transaction_hash.to_string());
             }
-            let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+        }
+    }
 
-            let gas = format_gas(U256::from(receipt.gas_used));
-            if !config.quiet {
-                println!("activated with {gas}");
-                println!(
-                    "activation tx hash: {}",
-                    receipt.transaction_hash.bright_magenta()
+    let report = DeployReport {
+        contract_address: program.to_string(),
+        deployment_tx_hash: receipt.transaction_hash.to_string(),
+        activation_tx_hash,
+        data_fee_wei,
+        gas_used,
+        activated,
+    };
+    reporter.result(&report, || "success!".bright_green().to_string());
+
+    Ok(receipt)
+}
+
+/// Sends `base_tx` (filling in a freshly fetched nonce and gas price on every
+/// attempt, the latter bumped over the previous attempt's -- see
+/// [`next_tx_params`]) and waits for its receipt, re-broadcasting up to
+/// `config.confirm.max_retries` times if it hasn't landed within
+/// `config.confirm.timeout` seconds -- covering a tx dropped from the
+/// mempool or one whose gas price was outbid. Once mined, waits for
+/// `config.confirm.confirmations` blocks before returning, so callers don't
+/// act on a receipt that a reorg could still undo.
+async fn send_and_confirm<P, T>(
+    provider: &P,
+    sender: Address,
+    base_tx: TransactionRequest,
+    config: &Deploy,
+    reporter: &Reporter,
+    label: &str,
+) -> Result<TransactionReceipt, KobaError>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    let timeout_duration = Duration::from_secs(config.confirm.timeout);
+    let max_attempts = config.confirm.max_retries + 1;
+    let mut last_gas_price = None;
+
+    for attempt in 1..=max_attempts {
+        let (nonce, gas_price) =
+            next_tx_params(provider, sender, last_gas_price, &config.retry).await?;
+        last_gas_price = Some(gas_price);
+        let tx = base_tx.clone().with_nonce(nonce).with_gas_price(gas_price);
+
+        reporter.status(format!(
+            "submitting {label} (attempt {attempt}/{max_attempts})"
+        ));
+
+        let pending = with_retry(&config.retry, || provider.send_transaction(tx.clone()))
+            .await
+            .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
+        let tx_hash = *pending.tx_hash();
+
+        match timeout(timeout_duration, pending.get_receipt()).await {
+            Ok(Ok(receipt)) => {
+                wait_for_confirmations(
+                    provider,
+                    &config.retry,
+                    &receipt,
+                    config.confirm.confirmations,
+                    reporter,
+                    label,
+                )
+                .await?;
+                return Ok(receipt);
+            }
+            Ok(Err(e)) => return Err(KobaError::Rpc(eyre::eyre!(e))),
+            Err(_) => {
+                reporter.status(
+                    format!(
+                        "{label} tx {tx_hash} not confirmed within {timeout_duration:?}, \
+                         re-broadcasting with a fresh nonce/gas price"
+                    )
+                    .yellow(),
                 );
             }
         }
     }
 
-    Ok(receipt)
+    Err(KobaError::Other(eyre::eyre!(
+        "{label} did not land after {max_attempts} attempt(s)"
+    )))
+}
+
+/// Minimum percentage a replacement transaction's gas price must clear a
+/// pending tx's by for most execution clients to accept the resubmission
+/// instead of rejecting it as "replacement transaction underpriced".
+const REPLACEMENT_GAS_PRICE_BUMP_PERCENT: u128 = 10;
+
+/// The nonce and gas price to use for the next broadcast of a transaction,
+/// fetched fresh so a retry after a dropped tx doesn't reuse a stale nonce.
+///
+/// `previous_gas_price` is the price the last attempt was sent with, if any.
+/// When present, the current market price is floored at
+/// `previous_gas_price` bumped by [`REPLACEMENT_GAS_PRICE_BUMP_PERCENT`] --
+/// resubmitting at the raw market price would get rejected as underpriced if
+/// the market hasn't moved since the last attempt, the exact situation a
+/// retry is meant to recover from.
+async fn next_tx_params<P, T>(
+    provider: &P,
+    sender: Address,
+    previous_gas_price: Option<u128>,
+    retry: &RetryConfig,
+) -> Result<(u64, u128), KobaError>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    let nonce = with_retry(retry, || provider.get_transaction_count(sender))
+        .await
+        .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
+    let market_price = with_retry(retry, || provider.get_gas_price())
+        .await
+        .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
+
+    let gas_price = match previous_gas_price {
+        Some(previous) => {
+            let min_bump = previous * (100 + REPLACEMENT_GAS_PRICE_BUMP_PERCENT) / 100;
+            market_price.max(min_bump)
+        }
+        None => market_price,
+    };
+
+    Ok((nonce, gas_price))
+}
+
+/// Polls for new blocks until `receipt` has `confirmations` block(s) behind
+/// it (a no-op for `confirmations <= 1`, since the receipt itself implies
+/// one).
+async fn wait_for_confirmations<P, T>(
+    provider: &P,
+    retry: &RetryConfig,
+    receipt: &TransactionReceipt,
+    confirmations: u64,
+    reporter: &Reporter,
+    label: &str,
+) -> Result<(), KobaError>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    if confirmations <= 1 {
+        return Ok(());
+    }
+    let Some(tx_block) = receipt.block_number else {
+        return Ok(());
+    };
+
+    loop {
+        let head = with_retry(retry, || provider.get_block_number())
+            .await
+            .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
+        let seen = head.saturating_sub(tx_block) + 1;
+        if seen >= confirmations {
+            return Ok(());
+        }
+        reporter.status(format!(
+            "waiting for {label} confirmations: {seen}/{confirmations}"
+        ));
+        sleep(Duration::from_secs(2)).await;
+    }
 }
 
 async fn get_activation_fee<P, T>(
     runtime: &[u8],
     provider: &P,
     sender: Address,
-) -> eyre::Result<Status>
+    retry: &RetryConfig,
+) -> Result<Status, KobaError>
 where
     P: Provider<T>,
     T: Transport + Clone,
@@ -181,13 +401,16 @@ where
         .with_input(tx_input)
         .with_value(parse_ether("1").unwrap());
 
-    if is_activated(&tx, &provider, &overrides).await? {
+    if is_activated(&tx, &provider, &overrides, retry).await? {
         return Ok(Status::Activated);
     }
 
-    let output = provider.call(&tx).overrides(&overrides).await?;
+    let output = with_retry(retry, || provider.call(&tx).overrides(&overrides))
+        .await
+        .map_err(|e| KobaError::Rpc(eyre::eyre!(e)))?;
     let ArbWasm::activateProgramReturn { dataFee, .. } =
-        ArbWasm::activateProgramCall::abi_decode_returns(&output, true)?;
+        ArbWasm::activateProgramCall::abi_decode_returns(&output, true)
+            .map_err(|e| KobaError::Other(eyre::eyre!(e)))?;
 
     Ok(Status::Created(dataFee))
 }
@@ -196,31 +419,35 @@ async fn is_activated<P, T>(
     tx: &TransactionRequest,
     provider: &P,
     overrides: &StateOverride,
-) -> eyre::Result<bool>
+    retry: &RetryConfig,
+) -> Result<bool, KobaError>
 where
     P: Provider<T>,
     T: Transport + Clone,
 {
-    match provider.call(tx).overrides(overrides).await {
+    match with_retry(retry, || provider.call(tx).overrides(overrides)).await {
         Ok(_) => Ok(false),
         Err(e) => {
             let raw_value = e
                 .as_error_resp()
-                .map(|payload| payload.data.clone())
-                .flatten()
-                .ok_or_eyre(format!("{e}"))
-                .wrap_err("could not check if the contract is activated")?;
-            let bytes: [u8; 4] = FromHex::from_hex(raw_value.get().trim_matches('"'))?;
+                .and_then(|payload| payload.data.clone())
+                .ok_or_else(|| KobaError::Rpc(eyre::eyre!("{e}")))?;
+            let bytes: [u8; 4] = FromHex::from_hex(raw_value.get().trim_matches('"'))
+                .map_err(|e| KobaError::Other(eyre::eyre!(e)))?;
 
             use ArbWasm::ArbWasmErrors as Errors;
-            match Errors::abi_decode(&bytes, true).wrap_err("unknown ArbWasm error")? {
+            let error = Errors::abi_decode(&bytes, true)
+                .map_err(|e| KobaError::Other(eyre::eyre!("unknown ArbWasm error: {e}")))?;
+            match error {
                 Errors::ProgramExpired(_) => Ok(false),
-                Errors::ProgramNotWasm(_) => bail!("not a Stylus program"),
+                Errors::ProgramNotWasm(_) => Err(KobaError::NotStylusProgram),
                 Errors::ProgramUpToDate(_) => Ok(true),
                 Errors::ProgramNotActivated(_) => Ok(false),
                 Errors::ProgramNeedsUpgrade(_) => Ok(false),
-                Errors::ProgramKeepaliveTooSoon(_) => bail!("unexpected ArbWasm error"),
-                Errors::ProgramInsufficientValue(_) => bail!("unexpected ArbWasm error"),
+                other @ (Errors::ProgramKeepaliveTooSoon(_)
+                | Errors::ProgramInsufficientValue(_)) => {
+                    Err(KobaError::ActivationRevert(other.into()))
+                }
             }
         }
     }
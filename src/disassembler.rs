@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use crate::{
+    assembler::{operand_size, Diagnostic, Opcode, Operator, Span, Token},
+    config::Disassemble,
+    error::KobaError,
+    reporter::{DisassembleReport, Reporter},
+};
+
+/// A single decoded instruction, tagged with where it sits in the original
+/// bytecode.
+struct Instruction {
+    offset: usize,
+    size: usize,
+    token: Token,
+}
+
+/// Disassembles raw, already-deployed bytecode back into a token stream.
+///
+/// Walks `bytecode` linearly, decoding one `Token::Opcode` per recognized
+/// opcode byte (consuming the following operand bytes for `PUSH1..PUSH32`
+/// into a `Token::Constant`), and rendering any byte that doesn't correspond
+/// to a known opcode as a `Token::Opcode` with a synthetic `invalid_0xXX`
+/// name, so the stream stays lossless and round-trips through
+/// [`Token::bytecode`] instead of being silently reinterpreted as data.
+///
+/// Every `JUMPDEST` targeted by some `PUSH`'s immediate operand is preceded
+/// by a `LabelBegin`/`LabelEnd` pair, and the `PUSH` that targets it is
+/// replaced with a bare `dataOffset` operator -- mirroring how the
+/// tokenizer represents a source-level `label:` and a reference to it.
+/// [`render`] turns the output back into text that [`assemble`] accepts,
+/// giving a round-trip verify/inspect workflow for already-deployed
+/// contracts.
+///
+/// A `PUSH` whose declared operand width runs past the end of `bytecode`
+/// (truncated trailing data, rather than a malformed stream) still decodes
+/// -- its `Token::Constant` just holds whatever bytes remain -- but is
+/// reported back as a [`Diagnostic`] instead of being silently clipped.
+///
+/// [`assemble`]: crate::assembler::assemble
+pub fn disassemble(bytecode: &[u8]) -> (Vec<Token>, Vec<Diagnostic>) {
+    let (instructions, diagnostics) = decode(bytecode);
+    let jump_targets = collect_jump_targets(&instructions);
+
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < instructions.len() {
+        let instruction = &instructions[i];
+
+        if is_jumpdest(&instruction.token) && jump_targets.contains(&instruction.offset) {
+            let label = label_name(instruction.offset);
+            tokens.push(Token::LabelBegin(label));
+            tokens.push(Token::LabelEnd);
+            tokens.push(instruction.token.clone());
+            i += 1;
+            continue;
+        }
+
+        if let Some(target) = push_target(&instructions, i) {
+            if jump_targets.contains(&target) {
+                tokens.push(Token::Operator(Operator {
+                    name: "dataOffset".to_owned(),
+                    arg: label_name(target),
+                }));
+                i += 2;
+                continue;
+            }
+        }
+
+        tokens.push(instruction.token.clone());
+        i += 1;
+    }
+
+    (tokens, diagnostics)
+}
+
+/// Renders [`disassemble`]'s tokens as assembly text that [`assemble`]
+/// accepts back. Each `LabelBegin`/`LabelEnd`/`JUMPDEST` triple becomes a
+/// single `label:` line, which the tokenizer expands back into those same
+/// three tokens; every other token is rendered via its `Display` impl.
+///
+/// [`assemble`]: crate::assembler::assemble
+pub fn render(tokens: &[Token]) -> String {
+    let mut lines = vec![];
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        lines.push(token.to_string());
+        if matches!(token, Token::LabelBegin(_)) {
+            // Skip the `LabelEnd` and `JUMPDEST` that `label:` stands for.
+            tokens.next();
+            tokens.next();
+        }
+    }
+    lines.join("\n")
+}
+
+/// Renders `bytecode`'s raw, un-labeled instructions as a human-auditable
+/// listing, one per line, each annotated with its byte offset and size
+/// (e.g. `jump // @0x1a size=1`). A `PUSH`'s operand bytes get a line of
+/// their own, e.g. `push2 // @0x1b size=1` then `0x0100 // @0x1c size=2`.
+pub fn annotate(bytecode: &[u8]) -> String {
+    decode(bytecode)
+        .0
+        .iter()
+        .map(|instruction| {
+            format!(
+                "{} // @{:#x} size={}",
+                instruction.token, instruction.offset, instruction.size
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decodes `bytecode` linearly into one [`Instruction`] per opcode, without
+/// attempting to reconstruct labels, alongside any [`Diagnostic`]s raised
+/// along the way (a truncated trailing `PUSH`).
+fn decode(bytecode: &[u8]) -> (Vec<Instruction>, Vec<Diagnostic>) {
+    let mut instructions = vec![];
+    let mut diagnostics = vec![];
+    let mut offset = 0;
+
+    while offset < bytecode.len() {
+        let byte = bytecode[offset];
+
+        if let Some(width) = push_operand_width(byte) {
+            let end = (offset + 1 + width).min(bytecode.len());
+            let operand = &bytecode[offset + 1..end];
+
+            if operand.len() < width {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "truncated {}: expected {width} operand byte(s) but only {} remain",
+                        instruction_name(byte),
+                        operand.len()
+                    ),
+                    Span { start: offset, end },
+                ));
+            }
+
+            instructions.push(Instruction {
+                offset,
+                size: 1,
+                token: Token::opcode(byte),
+            });
+            instructions.push(Instruction {
+                offset: offset + 1,
+                size: operand.len(),
+                token: Token::Constant(hex::encode(operand)),
+            });
+
+            offset = end;
+            continue;
+        }
+
+        match crate::assembler::instruction(byte) {
+            Some(_) => {
+                instructions.push(Instruction {
+                    offset,
+                    size: 1,
+                    token: Token::opcode(byte),
+                });
+            }
+            None => {
+                instructions.push(Instruction {
+                    offset,
+                    size: 1,
+                    token: Token::Opcode(Opcode {
+                        name: format!("invalid_{byte:#04x}"),
+                        hex: hex::encode([byte]),
+                    }),
+                });
+            }
+        }
+
+        offset += 1;
+    }
+
+    (instructions, diagnostics)
+}
+
+/// The mnemonic for `byte`, used only for diagnostic messages -- `byte` is
+/// always a recognized `PUSH` opcode here, so this never falls back to the
+/// `invalid_0xXX` synthetic name.
+fn instruction_name(byte: u8) -> String {
+    crate::assembler::instruction(byte).unwrap_or_else(|| "PUSH".to_owned())
+}
+
+/// If `byte` is `PUSH1..PUSH32`, the number of operand bytes it consumes.
+fn push_operand_width(byte: u8) -> Option<usize> {
+    let width = operand_size(byte);
+    (width > 0).then_some(width)
+}
+
+fn is_jumpdest(token: &Token) -> bool {
+    matches!(token, Token::Opcode(op) if op.name.eq_ignore_ascii_case("jumpdest"))
+}
+
+/// If `instructions[i]` is a `PUSH` immediately followed by its operand,
+/// the operand's value.
+fn push_target(instructions: &[Instruction], i: usize) -> Option<usize> {
+    let push = instructions.get(i)?;
+    let Token::Opcode(op) = &push.token else {
+        return None;
+    };
+    if !op.name.to_ascii_uppercase().starts_with("PUSH") || op.name.eq_ignore_ascii_case("push0") {
+        return None;
+    }
+
+    let Token::Constant(constant) = &instructions.get(i + 1)?.token else {
+        return None;
+    };
+    usize::from_str_radix(constant, 16).ok()
+}
+
+/// Every offset targeted by some `PUSH`'s immediate operand that also lands
+/// on a `JUMPDEST`.
+fn collect_jump_targets(instructions: &[Instruction]) -> HashSet<usize> {
+    let jumpdests: HashSet<usize> = instructions
+        .iter()
+        .filter(|i| is_jumpdest(&i.token))
+        .map(|i| i.offset)
+        .collect();
+
+    (0..instructions.len())
+        .filter_map(|i| push_target(instructions, i))
+        .filter(|target| jumpdests.contains(target))
+        .collect()
+}
+
+fn label_name(offset: usize) -> String {
+    format!("label_{offset:x}")
+}
+
+impl Disassemble {
+    pub fn run(&self) -> Result<(), KobaError> {
+        let reporter = Reporter::new(self.format, false);
+        let bytecode = hex::decode(self.bytecode.trim_start_matches("0x")).map_err(|e| {
+            KobaError::Other(eyre::eyre!("bytecode was not a valid hex string: {e}"))
+        })?;
+
+        let (tokens, diagnostics) = disassemble(&bytecode);
+        for diagnostic in &diagnostics {
+            reporter.status(format!("{diagnostic}"));
+        }
+
+        let report = DisassembleReport {
+            assembly: render(&tokens),
+            diagnostics: diagnostics.iter().map(ToString::to_string).collect(),
+        };
+        reporter.result(&report, || report.assembly.clone());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::{assemble, Severity};
+
+    use super::{disassemble, render};
+
+    #[test]
+    fn labels_a_jump_to_its_jumpdest() {
+        // push1 0x03, jump, jumpdest, stop
+        let bytecode = hex::decode("6003565b00").unwrap();
+        let (tokens, diagnostics) = disassemble(&bytecode);
+
+        assert!(diagnostics.is_empty());
+        let rendered = tokens
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            rendered,
+            "dataOffset(label_3)\njump\nlabel_3:\nlabelEnd\njumpdest\nstop"
+        );
+    }
+
+    #[test]
+    fn re_assembling_disassembled_output_reproduces_the_original_bytecode() {
+        // push1 0x03, jump, jumpdest, stop
+        let bytecode = hex::decode("6003565b00").unwrap();
+        let (tokens, diagnostics) = disassemble(&bytecode);
+        assert!(diagnostics.is_empty());
+
+        let rendered = render(&tokens);
+        let (reassembled, warnings, _) = assemble(&rendered, &[]).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(reassembled, bytecode);
+    }
+
+    #[test]
+    fn reports_a_truncated_trailing_push() {
+        // push4, but only two operand bytes remain.
+        let bytecode = hex::decode("63aabb").unwrap();
+        let (tokens, diagnostics) = disassemble(&bytecode);
+
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert_eq!("0xaabb", tokens[1].to_string());
+    }
+
+    #[test]
+    fn renders_unknown_opcode_bytes_as_invalid_losslessly() {
+        let bytecode = hex::decode("0c").unwrap(); // 0x0c has no assigned opcode.
+        let (tokens, diagnostics) = disassemble(&bytecode);
+
+        assert!(diagnostics.is_empty());
+        assert_eq!("invalid_0x0c", tokens[0].to_string());
+        assert_eq!(bytecode, tokens[0].bytecode().unwrap());
+    }
+}
@@ -0,0 +1,91 @@
+use alloy::primitives::U256;
+use thiserror::Error;
+
+use crate::deployer::ArbWasm;
+
+/// A decoded revert from the `ArbWasm` precompile.
+///
+/// Kept distinct from [`KobaError`] so call sites can match on the specific
+/// revert reason instead of re-parsing a formatted string.
+#[derive(Debug, Error)]
+pub enum ArbWasmError {
+    #[error("program is not a valid Stylus WASM binary")]
+    NotWasm,
+    #[error("program has not been activated")]
+    NotActivated,
+    #[error("program was activated with Stylus version {version}, node requires {stylus_version}")]
+    NeedsUpgrade { version: u16, stylus_version: u16 },
+    #[error("program activation expired {age_in_seconds}s ago")]
+    Expired { age_in_seconds: u64 },
+    #[error("program is already up to date")]
+    UpToDate,
+    #[error("program keepalive requested too soon ({age_in_seconds}s ago)")]
+    KeepaliveTooSoon { age_in_seconds: u64 },
+    #[error("insufficient value for activation: have {have}, want {want}")]
+    InsufficientValue { have: U256, want: U256 },
+}
+
+impl From<ArbWasm::ArbWasmErrors> for ArbWasmError {
+    fn from(err: ArbWasm::ArbWasmErrors) -> Self {
+        use ArbWasm::ArbWasmErrors as Errors;
+        match err {
+            Errors::ProgramNotWasm(_) => Self::NotWasm,
+            Errors::ProgramNotActivated(_) => Self::NotActivated,
+            Errors::ProgramNeedsUpgrade(e) => Self::NeedsUpgrade {
+                version: e.version,
+                stylus_version: e.stylusVersion,
+            },
+            Errors::ProgramExpired(e) => Self::Expired {
+                age_in_seconds: e.ageInSeconds,
+            },
+            Errors::ProgramUpToDate(_) => Self::UpToDate,
+            Errors::ProgramKeepaliveTooSoon(e) => Self::KeepaliveTooSoon {
+                age_in_seconds: e.ageInSeconds,
+            },
+            Errors::ProgramInsufficientValue(e) => Self::InsufficientValue {
+                have: e.have,
+                want: e.want,
+            },
+        }
+    }
+}
+
+/// Errors surfaced by Koba's public API.
+///
+/// Lets downstream tooling match on the cause of a failure (e.g. "not enough
+/// funds" vs "solc not found") instead of parsing an opaque [`eyre::Report`].
+#[derive(Debug, Error)]
+pub enum KobaError {
+    #[error("failed to read wasm file at {path}")]
+    WasmRead {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse wasm")]
+    WasmParse(#[source] eyre::Error),
+    #[error("failed to compress wasm bytes")]
+    Compression(#[source] std::io::Error),
+    #[error("solc not found; install the Solidity compiler to build the constructor")]
+    SolcNotFound,
+    #[error("failed to compile the constructor:\n{0}")]
+    SolcCompile(String),
+    #[error("not enough funds in account to pay for the data fee: have {have}, want {want}")]
+    InsufficientFunds { have: U256, want: U256 },
+    #[error("activation reverted: {0}")]
+    ActivationRevert(#[from] ArbWasmError),
+    #[error("RPC request failed")]
+    Rpc(#[source] eyre::Error),
+    #[error("not a Stylus program")]
+    NotStylusProgram,
+    #[error(
+        "node runs Stylus version {node}, which this build of koba does not support \
+         (supported: {min}-{max}); use a matching koba release or pass \
+         --allow-version-mismatch"
+    )]
+    UnsupportedStylusVersion { node: u16, min: u16, max: u16 },
+    #[error(transparent)]
+    Assemble(#[from] crate::assembler::AssembleError),
+    #[error(transparent)]
+    Other(#[from] eyre::Error),
+}
@@ -1,29 +1,66 @@
-use alloy::primitives::U256;
-use eyre::Context;
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue},
+    primitives::U256,
+};
 
-use crate::{assembler, config::Generate, solidity, wasm};
+use crate::{
+    assembler::{self, Diagnostic, GasEstimate},
+    config::Generate,
+    error::KobaError,
+    formatting::format_gas,
+    reporter::{GenerateReport, Reporter},
+    solidity, wasm,
+};
 
-/// Generate deployment transaction data for a Stylus contract.
-pub fn generate(config: &Generate) -> eyre::Result<Vec<u8>> {
-    config.generate()
+/// Generate deployment transaction data for a Stylus contract, alongside any
+/// non-fatal warnings noticed while assembling it and a static [`GasEstimate`]
+/// for the generated init code, for the caller to surface instead of
+/// silently dropping.
+pub fn generate(config: &Generate) -> Result<(Vec<u8>, Vec<Diagnostic>, GasEstimate), KobaError> {
+    config.generate().map(|(init_code, _, warnings, gas_estimate)| (init_code, warnings, gas_estimate))
 }
 
 impl Generate {
-    pub fn run(&self) -> eyre::Result<()> {
-        let generated = self.generate()?;
-        let generated = hex::encode(generated);
-        println!("{generated}");
+    pub fn run(&self) -> Result<(), KobaError> {
+        let reporter = Reporter::new(self.format, false);
+        let (generated, wasm_compressed_size, warnings, gas_estimate) = self.generate()?;
+        for warning in &warnings {
+            reporter.status(format!("{warning}"));
+        }
+        reporter.status(format!("estimated gas: {}", format_gas(U256::from(gas_estimate.total()))));
+
+        let report = GenerateReport {
+            init_code: hex::encode(&generated),
+            init_code_size: generated.len(),
+            wasm_compressed_size,
+            warnings: warnings.iter().map(ToString::to_string).collect(),
+            estimated_gas: gas_estimate.total(),
+            gas_estimate_data_dependent: gas_estimate.data_dependent,
+            legacy: self.legacy,
+        };
+        reporter.result(&report, || report.init_code.clone());
+
         Ok(())
     }
 
-    fn args(&self) -> eyre::Result<Vec<u8>> {
+    fn args(&self) -> Result<Vec<u8>, KobaError> {
+        if let Some(signature) = &self.constructor_signature {
+            return encode_constructor_args(signature, &self.constructor_arg);
+        }
+
         self.args
             .clone()
-            .map_or(Ok(vec![]), hex::decode)
-            .wrap_err("args were not proper hex strings")
+            .map_or(Ok(vec![]), |args| hex::decode(args))
+            .map_err(|e| KobaError::Other(eyre::eyre!("args were not proper hex strings: {e}")))
     }
 
-    fn generate(&self) -> eyre::Result<Vec<u8>> {
+    /// Returns the assembled init code alongside the compressed wasm's
+    /// length (so callers that need both, namely [`Generate::run`]'s report,
+    /// don't have to compress the wasm a second time just to read `.len()`),
+    /// any non-fatal warnings noticed while assembling, and a static
+    /// [`GasEstimate`], for the caller to surface instead of silently
+    /// dropping.
+    fn generate(&self) -> Result<(Vec<u8>, usize, Vec<Diagnostic>, GasEstimate), KobaError> {
         // User intends to deploy without constructor.
         if self.sol.is_none() {
             return self.plain_codegen();
@@ -31,14 +68,22 @@ impl Generate {
 
         let evmasm = solidity::assembly(self.sol.clone().unwrap())?;
         let wasm = wasm::compress(&self.wasm, self.legacy)?;
-        let asm = assembler::assemble(&evmasm, &wasm)?;
+        let wasm_compressed_size = wasm.len();
+        let (asm, warnings, gas_estimate) = assembler::assemble(&evmasm, &wasm)?;
         let args = self.args()?;
 
-        Ok([asm, args].concat())
+        Ok(([asm, args].concat(), wasm_compressed_size, warnings, gas_estimate))
     }
 
-    fn plain_codegen(&self) -> eyre::Result<Vec<u8>> {
+    /// Constructor-less deployments skip the assembler entirely (the init
+    /// code is a fixed prelude, not assembled token stream), so there's
+    /// nothing for [`estimate_gas`] to walk; the estimate is just the zero
+    /// default.
+    ///
+    /// [`estimate_gas`]: crate::assembler::estimate_gas
+    fn plain_codegen(&self) -> Result<(Vec<u8>, usize, Vec<Diagnostic>, GasEstimate), KobaError> {
         let wasm = wasm::compress(&self.wasm, self.legacy)?;
+        let wasm_compressed_size = wasm.len();
 
         let mut init_code = Vec::with_capacity(42 + wasm.len());
         init_code.push(0x7f); // PUSH32
@@ -54,6 +99,119 @@ impl Generate {
         init_code.push(0xf3); // RETURN
         init_code.extend(wasm);
 
-        Ok(init_code)
+        Ok((init_code, wasm_compressed_size, vec![], GasEstimate::default()))
+    }
+}
+
+/// ABI-encodes `values` against the parameter types of a human-readable
+/// constructor `signature`, e.g. `constructor(address,uint256,string)`.
+fn encode_constructor_args(signature: &str, values: &[String]) -> Result<Vec<u8>, KobaError> {
+    let types = parse_constructor_types(signature)?;
+    if types.len() != values.len() {
+        return Err(KobaError::Other(eyre::eyre!(
+            "constructor '{signature}' expects {} argument(s), got {}",
+            types.len(),
+            values.len()
+        )));
+    }
+
+    let values = types
+        .iter()
+        .zip(values)
+        .map(|(ty, value)| {
+            ty.coerce_str(value).map_err(|e| {
+                KobaError::Other(eyre::eyre!(
+                    "failed to encode constructor argument '{value}' as `{ty}`: {e}"
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, KobaError>>()?;
+
+    Ok(DynSolValue::Tuple(values).abi_encode_params())
+}
+
+/// Parses the parameter types out of a constructor signature, e.g.
+/// `constructor(address,uint256)` -> `[address, uint256]`.
+fn parse_constructor_types(signature: &str) -> Result<Vec<DynSolType>, KobaError> {
+    let body = signature.trim().strip_prefix("constructor").unwrap_or(signature.trim());
+    let body = body
+        .trim()
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(|| KobaError::Other(eyre::eyre!("invalid constructor signature '{signature}'")))?;
+
+    if body.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    split_top_level_commas(body)
+        .into_iter()
+        .map(|ty| {
+            DynSolType::parse(ty.trim())
+                .map_err(|e| KobaError::Other(eyre::eyre!("invalid constructor type '{ty}': {e}")))
+        })
+        .collect()
+}
+
+/// Splits a comma-separated type list at depth 0, respecting nested
+/// parentheses (tuples) and brackets (arrays).
+fn split_top_level_commas(body: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&body[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_constructor_args, parse_constructor_types, split_top_level_commas};
+
+    #[test]
+    fn parses_a_multi_arg_signature() {
+        let types = parse_constructor_types("constructor(address,uint256,string)").unwrap();
+        assert_eq!(format!("{types:?}"), "[Address, Uint(256), String]");
+    }
+
+    #[test]
+    fn parses_a_nested_tuple_type() {
+        let types = parse_constructor_types("constructor((address,uint256)[])").unwrap();
+        assert_eq!(types.len(), 1);
+        assert_eq!(format!("{:?}", types[0]), "Array(Tuple([Address, Uint(256)]))");
+    }
+
+    #[test]
+    fn splits_top_level_commas_around_nested_tuples() {
+        let parts = split_top_level_commas("(address,uint256),uint256[],bool");
+        assert_eq!(parts, vec!["(address,uint256)", "uint256[]", "bool"]);
+    }
+
+    #[test]
+    fn encode_constructor_args_rejects_an_arg_count_mismatch() {
+        let signature = "constructor(address,uint256)";
+        let values = vec!["0x0000000000000000000000000000000000000001".to_owned()];
+        let err = encode_constructor_args(signature, &values).unwrap_err();
+        assert!(err.to_string().contains("expects 2 argument(s), got 1"));
+    }
+
+    #[test]
+    fn encodes_a_multi_arg_signature() {
+        let signature = "constructor(address,uint256)";
+        let values = vec!["0x0000000000000000000000000000000000000001".to_owned(), "42".to_owned()];
+        let encoded = encode_constructor_args(signature, &values).unwrap();
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(encoded[31], 1);
+        assert_eq!(encoded[63], 42);
     }
 }
@@ -2,12 +2,18 @@ mod assembler;
 pub mod config;
 mod constants;
 mod deployer;
+pub mod disassembler;
+pub mod error;
 mod formatting;
 mod generator;
+mod reporter;
+mod retry;
 mod solidity;
 mod wallet;
 mod wasm;
 
 pub use config::run;
 pub use deployer::deploy;
+pub use disassembler::disassemble;
+pub use error::KobaError;
 pub use generator::generate;
@@ -0,0 +1,89 @@
+use std::fmt::Display;
+
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::config::OutputFormat;
+
+/// The result of a `generate` invocation, emitted as a single JSON record in
+/// [`OutputFormat::Json`] mode.
+#[derive(Debug, Serialize)]
+pub struct GenerateReport {
+    pub init_code: String,
+    pub init_code_size: usize,
+    pub wasm_compressed_size: usize,
+    /// Any non-fatal problems noticed while assembling, rendered to text.
+    pub warnings: Vec<String>,
+    /// A static, worst-case gas estimate for deploying and running the
+    /// generated init code once; see [`GasEstimate`](crate::assembler::GasEstimate).
+    /// Zero when there is no Solidity constructor, since that init code is
+    /// not assembled.
+    pub estimated_gas: u64,
+    /// Set when `estimated_gas` is a lower bound rather than an exact
+    /// figure, because some opcode's true cost depended on data not knowable
+    /// statically.
+    pub gas_estimate_data_dependent: bool,
+    pub legacy: bool,
+}
+
+/// The result of a `deploy` invocation, emitted as a single JSON record in
+/// [`OutputFormat::Json`] mode.
+#[derive(Debug, Serialize)]
+pub struct DeployReport {
+    pub contract_address: String,
+    pub deployment_tx_hash: String,
+    pub activation_tx_hash: Option<String>,
+    pub data_fee_wei: Option<String>,
+    pub gas_used: Option<u128>,
+    pub activated: bool,
+}
+
+/// The result of a `disassemble` invocation, emitted as a single JSON
+/// record in [`OutputFormat::Json`] mode.
+#[derive(Debug, Serialize)]
+pub struct DisassembleReport {
+    pub assembly: String,
+    /// Any problems noticed while decoding (e.g. a truncated trailing
+    /// `PUSH`), rendered to text.
+    pub diagnostics: Vec<String>,
+}
+
+/// Routes `generate` and `deploy`'s progress and result output through one
+/// path, so `--quiet` and `--format json` don't each need their own
+/// scattered `if` checks around every `println!`. Human-readable, colored
+/// output (via `owo_colors`) only ever happens through this type, and only
+/// when `format` is [`OutputFormat::Human`].
+pub struct Reporter {
+    format: OutputFormat,
+    quiet: bool,
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat, quiet: bool) -> Self {
+        Self { format, quiet }
+    }
+
+    /// Prints a human-readable progress line. No-op in JSON mode, or when
+    /// `--quiet` is set.
+    pub fn status(&self, message: impl Display) {
+        if self.quiet || self.format == OutputFormat::Json {
+            return;
+        }
+        println!("{message}");
+    }
+
+    /// Emits the final result: a single JSON line in JSON mode (printed even
+    /// under `--quiet`, since it's the point of the invocation), or
+    /// `human()`'s output in human-readable mode (suppressed under
+    /// `--quiet`).
+    pub fn result<T: Serialize>(&self, record: &T, human: impl FnOnce() -> String) {
+        match self.format {
+            OutputFormat::Json => match serde_json::to_string(record) {
+                Ok(json) => println!("{json}"),
+                Err(e) => eprintln!("{}", format!("failed to serialize result: {e}").red()),
+            },
+            OutputFormat::Human if !self.quiet => println!("{}", human()),
+            OutputFormat::Human => {}
+        }
+    }
+}
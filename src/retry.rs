@@ -0,0 +1,57 @@
+use std::{future::Future, time::Duration};
+
+use alloy::transports::{RpcError, TransportErrorKind};
+use rand::Rng;
+use tokio::time::sleep;
+
+use crate::config::RetryConfig;
+
+/// Classifies whether a failed RPC call is safe to retry.
+///
+/// Transport/connection errors, timeouts, and HTTP 429/5xx rate-limit
+/// responses are retryable. A decodable `ArbWasm` revert is deterministic and
+/// must never be retried.
+pub fn is_retryable(err: &RpcError<TransportErrorKind>) -> bool {
+    if let Some(resp) = err.as_error_resp() {
+        // The node understood the call and returned a JSON-RPC error.
+        // A decoded `ArbWasm` revert (or any other application error) is
+        // deterministic, so only rate-limit/upstream-failure style codes
+        // are worth retrying.
+        return matches!(resp.code, 429 | 500..=599);
+    }
+
+    // No decodable error response: a transport-level failure such as a
+    // dropped connection or a timeout, which is always worth retrying.
+    matches!(err, RpcError::Transport(_) | RpcError::NullResp)
+}
+
+/// Retries `attempt` with exponential backoff and full jitter, honoring
+/// `config`'s `max_attempts`, `base_interval`, and `max_interval`.
+///
+/// For attempt `n` (0-indexed), sleeps `rand(0, min(max_interval, base_interval
+/// * 2^n))` before trying again. Non-retryable errors (see [`is_retryable`])
+/// are surfaced immediately; the last error is surfaced once `max_attempts` is
+/// exhausted.
+pub async fn with_retry<F, Fut, T>(
+    config: &RetryConfig,
+    mut attempt: F,
+) -> Result<T, RpcError<TransportErrorKind>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RpcError<TransportErrorKind>>>,
+{
+    let max_attempts = config.max_attempts.max(1);
+    for n in 0..max_attempts {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(err) if n + 1 < max_attempts && is_retryable(&err) => {
+                let cap = config.max_interval().min(config.base_interval() * 2u32.pow(n));
+                let backoff = rand::thread_rng().gen_range(Duration::ZERO..=cap);
+                sleep(backoff).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    unreachable!("loop always returns on the last attempt")
+}
@@ -1,22 +1,28 @@
-use std::{path::Path, process::Command};
+use std::{io, path::Path, process::Command};
 
-use eyre::bail;
+use crate::error::KobaError;
 
-pub fn assembly(sol_path: impl AsRef<Path>) -> eyre::Result<String> {
+pub fn assembly(sol_path: impl AsRef<Path>) -> Result<String, KobaError> {
     let sol_path = sol_path.as_ref();
     if !sol_path.exists() {
-        bail!("failed to read Solidity constructor file");
+        return Err(KobaError::Other(eyre::eyre!(
+            "failed to read Solidity constructor file"
+        )));
     }
 
     let output = Command::new("solc")
         .arg(sol_path)
         .arg("--asm")
         .arg("--optimize")
-        .output()?;
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => KobaError::SolcNotFound,
+            _ => KobaError::Other(e.into()),
+        })?;
     let code = String::from_utf8_lossy(&output.stdout);
     if code.is_empty() {
         let err = String::from_utf8_lossy(&output.stderr);
-        bail!("failed to compile the constructor:\n{err}");
+        return Err(KobaError::SolcCompile(err.into_owned()));
     }
 
     let code = code
@@ -1,18 +1,188 @@
-use alloy::signers::local::{LocalSigner, PrivateKeySigner};
+use alloy::{
+    primitives::{Address, ChainId, B256},
+    signers::{
+        ledger::{HDPath as LedgerHDPath, LedgerSigner},
+        local::{coins_bip39::English, LocalSigner, MnemonicBuilder, PrivateKeySigner},
+        trezor::{HDPath as TrezorHDPath, TrezorSigner},
+        Signature, Signer,
+    },
+};
 use eyre::{eyre, Context, Result};
 use std::fs;
 
 use crate::config::PrivateKey;
 
+/// The default BIP-32 path Koba derives a signing key from, with
+/// `{account_index}` standing in for the final component.
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+
+/// A signing backend `koba` can deploy through.
+///
+/// Every variant implements [`Signer`], so the deploy flow never needs to
+/// know which one it's holding -- it just signs. `Remote` in particular
+/// never materializes the private key in this process at all; it forwards
+/// the hash to be signed to an external endpoint and relays back the
+/// signature.
+pub enum Wallet {
+    /// A key held in memory, whether parsed from a hex string, a keystore,
+    /// or derived from a mnemonic.
+    Local(PrivateKeySigner),
+    /// A Ledger hardware wallet, reached over USB/HID.
+    Ledger(LedgerSigner),
+    /// A Trezor hardware wallet, reached over USB/HID.
+    Trezor(TrezorSigner),
+    /// A remote signer that receives a hash to sign over HTTP and never
+    /// hands the key to this process.
+    Remote(RemoteSigner),
+}
+
+#[async_trait::async_trait]
+impl Signer for Wallet {
+    async fn sign_hash(&self, hash: &B256) -> alloy::signers::Result<Signature> {
+        match self {
+            Wallet::Local(signer) => signer.sign_hash(hash).await,
+            Wallet::Ledger(signer) => signer.sign_hash(hash).await,
+            Wallet::Trezor(signer) => signer.sign_hash(hash).await,
+            Wallet::Remote(signer) => signer.sign_hash(hash).await,
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Wallet::Local(signer) => signer.address(),
+            Wallet::Ledger(signer) => signer.address(),
+            Wallet::Trezor(signer) => signer.address(),
+            Wallet::Remote(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        match self {
+            Wallet::Local(signer) => signer.chain_id(),
+            Wallet::Ledger(signer) => signer.chain_id(),
+            Wallet::Trezor(signer) => signer.chain_id(),
+            Wallet::Remote(signer) => signer.chain_id(),
+        }
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        match self {
+            Wallet::Local(signer) => signer.set_chain_id(chain_id),
+            Wallet::Ledger(signer) => signer.set_chain_id(chain_id),
+            Wallet::Trezor(signer) => signer.set_chain_id(chain_id),
+            Wallet::Remote(signer) => signer.set_chain_id(chain_id),
+        }
+    }
+}
+
+/// Signs over an HTTP(S) endpoint instead of holding a key locally.
+///
+/// `koba` speaks a minimal protocol against `endpoint`: `GET /address`
+/// returns the signing address as a JSON string, and `POST /sign` takes
+/// `{"hash": "0x.."}` and returns `{"signature": "0x.."}`. This lets an
+/// institutional signer (an HSM, a multisig co-signer service, a custody
+/// provider's API) sit behind the endpoint without koba ever seeing the
+/// private key.
+pub struct RemoteSigner {
+    endpoint: String,
+    address: Address,
+    chain_id: Option<ChainId>,
+    client: reqwest::Client,
+}
+
+impl RemoteSigner {
+    /// Looks up the signing address at `endpoint` and returns a signer bound
+    /// to it.
+    pub async fn new(endpoint: String) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let address = client
+            .get(format!("{endpoint}/address"))
+            .send()
+            .await
+            .wrap_err("could not reach remote signer")?
+            .json::<Address>()
+            .await
+            .wrap_err("remote signer returned an invalid address")?;
+
+        Ok(Self {
+            endpoint,
+            address,
+            chain_id: None,
+            client,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for RemoteSigner {
+    async fn sign_hash(&self, hash: &B256) -> alloy::signers::Result<Signature> {
+        #[derive(serde::Serialize)]
+        struct Request {
+            hash: B256,
+        }
+        #[derive(serde::Deserialize)]
+        struct Response {
+            signature: Signature,
+        }
+
+        let response: Response = self
+            .client
+            .post(format!("{}/sign", self.endpoint))
+            .json(&Request { hash: *hash })
+            .send()
+            .await
+            .map_err(alloy::signers::Error::other)?
+            .json()
+            .await
+            .map_err(alloy::signers::Error::other)?;
+
+        Ok(response.signature)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        self.chain_id = chain_id;
+    }
+}
+
 impl PrivateKey {
-    pub fn wallet(&self) -> Result<PrivateKeySigner> {
+    pub async fn wallet(&self) -> Result<Wallet> {
         if let Some(key) = &self.private_key {
-            return Ok(key.parse::<PrivateKeySigner>()?);
+            return Ok(Wallet::Local(key.parse::<PrivateKeySigner>()?));
         }
 
         if let Some(file) = &self.private_key_path {
             let key = fs::read_to_string(file).wrap_err("could not open private key file")?;
-            return Ok(key.parse::<PrivateKeySigner>()?);
+            return Ok(Wallet::Local(key.parse::<PrivateKeySigner>()?));
+        }
+
+        if let Some(phrase) = self.mnemonic_phrase()? {
+            return Ok(Wallet::Local(self.mnemonic_wallet(&phrase)?));
+        }
+
+        if self.ledger {
+            let signer = LedgerSigner::new(self.ledger_derivation_path(), None)
+                .await
+                .wrap_err("could not connect to Ledger device")?;
+            return Ok(Wallet::Ledger(signer));
+        }
+
+        if self.trezor {
+            let signer = TrezorSigner::new(self.trezor_derivation_path(), None)
+                .await
+                .wrap_err("could not connect to Trezor device")?;
+            return Ok(Wallet::Trezor(signer));
+        }
+
+        if let Some(url) = &self.remote_signer_url {
+            return Ok(Wallet::Remote(RemoteSigner::new(url.clone()).await?));
         }
 
         let keystore = self
@@ -25,6 +195,54 @@ impl PrivateKey {
             .map(fs::read_to_string)
             .unwrap_or(Ok("".into()))?;
 
-        LocalSigner::decrypt_keystore(keystore, password).wrap_err("could not decrypt keystore")
+        Ok(Wallet::Local(
+            LocalSigner::decrypt_keystore(keystore, password)
+                .wrap_err("could not decrypt keystore")?,
+        ))
+    }
+
+    fn mnemonic_phrase(&self) -> Result<Option<String>> {
+        if let Some(phrase) = &self.mnemonic {
+            return Ok(Some(phrase.clone()));
+        }
+
+        self.mnemonic_path
+            .as_ref()
+            .map(|file| {
+                fs::read_to_string(file)
+                    .wrap_err("could not open mnemonic file")
+                    .map(|phrase| phrase.trim().to_owned())
+            })
+            .transpose()
+    }
+
+    fn mnemonic_wallet(&self, phrase: &str) -> Result<PrivateKeySigner> {
+        let mut builder = MnemonicBuilder::<English>::default().phrase(phrase);
+
+        if let Some(passphrase) = &self.mnemonic_passphrase {
+            builder = builder.password(passphrase);
+        }
+
+        builder = match &self.derivation_path {
+            Some(path) => builder.derivation_path(path)?,
+            None => builder
+                .derivation_path(format!("{DEFAULT_DERIVATION_PATH}/{}", self.account_index))?,
+        };
+
+        builder.build().wrap_err("could not derive key from mnemonic")
+    }
+
+    fn ledger_derivation_path(&self) -> LedgerHDPath {
+        match &self.derivation_path {
+            Some(path) => LedgerHDPath::Other(path.clone()),
+            None => LedgerHDPath::LedgerLive(self.account_index as usize),
+        }
+    }
+
+    fn trezor_derivation_path(&self) -> TrezorHDPath {
+        match &self.derivation_path {
+            Some(path) => TrezorHDPath::Other(path.clone()),
+            None => TrezorHDPath::TrezorLive(self.account_index as usize),
+        }
     }
 }
@@ -5,7 +5,8 @@ use std::{
 };
 
 use brotli2::read::BrotliEncoder;
-use eyre::Context;
+
+use crate::error::KobaError;
 
 pub const COMPRESSION_LEVEL: u32 = 11;
 pub const EOF_PREFIX_TESTNET_V1: &str = "EFF000";
@@ -13,11 +14,13 @@ pub const EOF_PREFIX: &str = "EFF00000";
 
 /// Reads a webassembly file at the specified `path` and attempts to compress
 /// it.
-pub fn compress(path: impl AsRef<Path>, legacy: bool) -> eyre::Result<Vec<u8>> {
+pub fn compress(path: impl AsRef<Path>, legacy: bool) -> Result<Vec<u8>, KobaError> {
     let path = path.as_ref();
-    let wasm = fs::read(path)
-        .wrap_err_with(|| eyre::eyre!("failed to read wasm {}", path.to_string_lossy()))?;
-    let wasm = wasmer::wat2wasm(&wasm).wrap_err("failed to parse wasm")?;
+    let wasm = fs::read(path).map_err(|source| KobaError::WasmRead {
+        path: path.to_string_lossy().into_owned(),
+        source,
+    })?;
+    let wasm = wasmer::wat2wasm(&wasm).map_err(|e| KobaError::WasmParse(eyre::eyre!(e)))?;
 
     let stream = Cursor::new(wasm);
     let mut compressor = BrotliEncoder::new(stream, COMPRESSION_LEVEL);
@@ -30,7 +33,7 @@ pub fn compress(path: impl AsRef<Path>, legacy: bool) -> eyre::Result<Vec<u8>> {
     let mut contract_code = hex::decode(prefix).unwrap();
     compressor
         .read_to_end(&mut contract_code)
-        .wrap_err("failed to compress wasm bytes")?;
+        .map_err(KobaError::Compression)?;
 
     Ok(contract_code)
 }